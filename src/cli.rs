@@ -1,5 +1,9 @@
+use std::path::PathBuf;
+
 use argh::FromArgs;
 
+use crate::types::Network;
+
 #[derive(FromArgs)]
 #[allow(dead_code)]
 /// XMTP CLI Chat Application
@@ -11,4 +15,26 @@ pub struct XChatApp {
     /// use xchat against a local XMTP deployment
     #[argh(switch)]
     pub local: bool,
+
+    /// capture the mouse to click and resize panes, instead of selecting text in the terminal
+    #[argh(switch)]
+    pub mouse: bool,
+
+    /// write every action to a file, to be replayed when reporting a bug
+    #[argh(option)]
+    pub record: Option<PathBuf>,
+
+    /// replay actions written with --record, instead of connecting to XMTP
+    #[argh(option)]
+    pub replay: Option<PathBuf>,
+}
+
+impl XChatApp {
+    pub fn network(&self) -> Network {
+        if self.local {
+            Network::Local
+        } else {
+            Network::Dev
+        }
+    }
 }
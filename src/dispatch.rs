@@ -1,81 +1,129 @@
 //! Dispatcher for our stores
+mod bus;
 mod commands;
+pub mod recording;
 mod xmtp;
 
 use std::{
     collections::HashMap,
     future::{self, Future},
+    path::PathBuf,
     pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 use anyhow::Result;
+pub use bus::*;
 pub use commands::*;
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyEvent, MouseEvent};
 use futures::future::join_all;
 use ratatui::{prelude::Rect, Frame};
-use tokio::sync::broadcast::{self, Receiver};
+use serde::{Deserialize, Serialize};
 pub use xmtp::*;
 use xmtp_mls::storage::group_message::StoredGroupMessage;
 
-use crate::types::{Group, GroupId};
+use crate::types::{
+    AppError, ConnectionState, ErrorSource, Group, GroupId, GroupInfo, HotlistEntry, Identity,
+    InboxState, InputMode, Member, Network, Page,
+};
+
+/// Whether the UI changed since the last frame was drawn
+static DIRTY: AtomicBool = AtomicBool::new(true);
 
-/// Generic Dispatcher that dispatches actions
-pub struct Dispatcher<'a> {
-    stores: Vec<&'a mut dyn Store>,
-    events: &'a mut Receiver<Action>,
+/// Draw a new frame. Stores call this when an action changes what they show, so the idle UI is
+/// not drawn again for actions nobody sees, like mouse motion or records for the log buffer.
+pub fn mark_dirty() {
+    DIRTY.store(true, Ordering::Relaxed);
 }
 
-impl<'a> Dispatcher<'a> {
-    pub fn new(stores: Vec<&'a mut dyn Store>, events: &'a mut Receiver<Action>) -> Self {
-        Self { stores, events }
-    }
+pub fn is_dirty() -> bool {
+    DIRTY.load(Ordering::Relaxed)
+}
 
-    /// Asyncronously broadcasts an action to all `Stores`. Logs any errors resulting from them.
-    pub async fn dispatch(&mut self) -> Action {
-        let action = self.get_action().await;
+pub fn clear_dirty() {
+    DIRTY.store(false, Ordering::Relaxed);
+}
 
-        if let Action::Quit = action {
-            return Action::Quit;
-        }
+/// Generic Dispatcher that dispatches actions to a tree of stores
+pub struct Dispatcher<'a, S> {
+    root: S,
+    events: &'a mut ActionReceiver,
+}
 
-        let mut dispatches = vec![];
+impl<'a, S: Store> Dispatcher<'a, S> {
+    pub fn new(root: S, events: &'a mut ActionReceiver) -> Self {
+        Self { root, events }
+    }
 
-        for store in self.stores.iter_mut() {
-            dispatches.push(store.update(action.clone()));
-        }
+    /// The store at the root of the tree, i.e to render it
+    pub fn root(&self) -> &S {
+        &self.root
+    }
+
+    /// Wait for the next action to dispatch
+    pub async fn next(&mut self) -> Action {
+        self.get_action().await
+    }
 
-        let results = join_all(dispatches).await;
-        for result in results {
-            match result {
-                Err(e) => log::error!("{}", e),
-                _ => (),
+    /// Asyncronously broadcasts an action to all `Stores`. Errors resulting from them are logged
+    /// and dispatched again as [`Action::Error`] to be shown to the user.
+    pub async fn dispatch(&mut self, action: Action) {
+        if action != Action::Tick {
+            crate::errors::record_action(&action);
+        }
+        let is_error = matches!(action, Action::Error(_));
+        for e in self.update_stores(action).await {
+            log::error!("{}", e);
+            // an error showing an error is only logged
+            if is_error {
+                continue;
+            }
+            let error = Action::Error(AppError::new(ErrorSource::View, vec![0], e));
+            for e in self.update_stores(error).await {
+                log::error!("{}", e);
             }
         }
-        Action::Noop
+    }
+
+    async fn update_stores(&mut self, action: Action) -> Vec<anyhow::Error> {
+        update_tree(&mut self.root, &action).await
     }
 
     async fn get_action(&mut self) -> Action {
-        use broadcast::error::RecvError::*;
-        let mut action: Option<Action> = Option::None;
-
-        while action.is_none() {
-            action = match self.events.recv().await {
-                Ok(Action::Quit) => Some(Action::Quit),
-                Err(Closed) => Some(Action::Quit),
-                Err(Lagged(msgs)) => {
-                    log::warn!("Dispatcher lagged {msgs}");
-                    None
-                }
-                Ok(action) => Some(action),
-            };
-        }
-        action.expect("Action must be some to exit infinite loop")
+        self.events.recv().await.unwrap_or(Action::Quit)
     }
 }
 
+/// Update `store` if it handles `action`, then its children concurrently
+fn update_tree<'a>(
+    store: &'a mut dyn Store,
+    action: &'a Action,
+) -> Pin<Box<dyn Future<Output = Vec<anyhow::Error>> + 'a>> {
+    Box::pin(async move {
+        let mut errors = Vec::new();
+        if store.handles(action) {
+            if let Err(e) = store.update(action.clone()).await {
+                errors.push(e);
+            }
+        }
+        let children = store.stores().into_iter().map(|child| update_tree(child, action));
+        errors.extend(join_all(children).await.into_iter().flatten());
+        errors
+    })
+}
+
 /// Trait that stores some state that is updated based on the defined [`Action`]
 pub trait Store {
+    /// Whether [`Store::update`] should be called with `action`. Stores only handling a few kinds
+    /// of actions should say so, to not be sent everything else.
+    fn handles(&self, _action: &Action) -> bool {
+        true
+    }
     fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>>;
+    /// Stores nested in this one, which are dispatched to after it
     fn stores(&mut self) -> Vec<&mut dyn Store> {
         Vec::new()
     }
@@ -110,23 +158,72 @@ pub trait PageRender {
 
 /// Everything that can happen in the applications
 #[allow(dead_code)]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Action {
     Quit,
+    /// Take the terminal over again after a [`Action::Suspend`]
     Resume,
+    /// Stop the process with SIGTSTP, giving the terminal back to the shell
     Suspend,
+    /// Hand the terminal over to `$EDITOR` to edit the file
+    RunEditor(PathBuf),
+    /// Text written in `$EDITOR`, replacing the draft in the input box
+    Composed(String),
+    /// Sent every second, for views showing something that changes with time
     Tick,
     RenderTick,
     KeyPress(KeyEvent),
+    Mouse(MouseEvent),
+    /// Start or stop capturing mouse events
+    ToggleMouse,
+    /// Show or hide the nicklist pane
+    ToggleNicklist,
+    Window(WindowCommand),
+    /// A popup opened (`true`) or closed (`false`). Key presses are meant for the popup while
+    /// it is open.
+    SetPopup(bool),
+    /// Text pasted into the terminal
+    Paste(String),
+    FocusGained,
+    FocusLost,
     Resize(u16, u16),
     EnterNormal,
     EnterInsert,
     FakeMessage(GroupId, (String, String)),
-    ReceiveMessages(HashMap<GroupId, Vec<StoredGroupMessage>>),
-    ReceiveMessage(StoredGroupMessage),
+    #[serde(with = "recording::by_group")]
+    ReceiveMessages(Arc<HashMap<GroupId, Vec<StoredGroupMessage>>>),
+    ReceiveMessage(Arc<StoredGroupMessage>),
     SetFocusedGroup(Group),
     NewGroups(Vec<Group>),
+    /// Forget the groups of the previous wallet, sent when `/generate` switched to a new one
+    ClearGroups,
+    /// Focus the room at this index
     ChangeRoom(usize),
+    /// Focus the room with this name
+    ChangeRoomByName(String),
+    /// Send a message to the room with this number or name, without focusing it
+    SendToBuffer(String, String),
+    /// Buffers with unread activity, ordered by buffer number
+    Hotlist(Vec<HotlistEntry>),
+    /// The identity of this session, sent once the XMTP client is ready
+    Identity(Identity),
+    /// Something failed, to be shown as a toast and in the buffer it happened in
+    Error(AppError),
+    Connection(Network, ConnectionState),
+    /// The current members of a group
+    GroupMembers(GroupId, Vec<Member>),
+    GroupInfo(GroupInfo),
+    /// Our inbox state, or why it could not be fetched
+    InboxState(Result<InboxState, String>),
+    /// Print a table of the groups or of the users we know of in the xchat buffer
+    List(ListCommand),
+    /// Show a page over the chat
+    OpenPage(Page),
+    /// Go back to the page under the current one
+    ClosePage,
+    InputMode(InputMode),
+    /// Amount of messages waiting to be sent
+    Outbox(usize),
     XMTP(XMTPAction),
     Command(CommandAction),
     Noop,
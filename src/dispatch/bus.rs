@@ -0,0 +1,133 @@
+//! The action bus.
+//! Every part of the app sends [`Action`]s to the one [`Dispatcher`](super::Dispatcher). The queue
+//! is unbounded, since stores send actions while they are being dispatched to, so nothing is ever
+//! dropped. Instead, actions where only the latest one matters, like a resize, are coalesced.
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use super::Action;
+
+pub type ActionSender = UnboundedSender<Action>;
+
+/// How often the depth of the queue is logged
+const METRICS_INTERVAL: Duration = Duration::from_secs(10);
+/// Amount of waiting actions past which the dispatcher is likely falling behind
+const HIGH_WATER: usize = 1_000;
+
+pub fn channel() -> (ActionSender, ActionReceiver) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (tx, ActionReceiver::new(rx))
+}
+
+pub struct ActionReceiver {
+    rx: UnboundedReceiver<Action>,
+    /// Actions received but not yet dispatched
+    queue: VecDeque<Action>,
+    metrics: Metrics,
+}
+
+#[derive(Debug)]
+struct Metrics {
+    received: usize,
+    coalesced: usize,
+    max_depth: usize,
+    reported: Instant,
+}
+
+impl ActionReceiver {
+    fn new(rx: UnboundedReceiver<Action>) -> Self {
+        let metrics = Metrics { received: 0, coalesced: 0, max_depth: 0, reported: Instant::now() };
+        Self { rx, queue: VecDeque::new(), metrics }
+    }
+
+    /// The next action, or `None` once every sender is dropped
+    pub async fn recv(&mut self) -> Option<Action> {
+        if self.queue.is_empty() {
+            let action = self.rx.recv().await?;
+            self.push(action);
+        }
+        while let Ok(action) = self.rx.try_recv() {
+            self.push(action);
+        }
+        self.report();
+        self.queue.pop_front()
+    }
+
+    fn push(&mut self, action: Action) {
+        self.metrics.received += 1;
+        // only the latest size matters, replacing the one right before it keeps the order
+        if let (Action::Resize(..), Some(queued @ Action::Resize(..))) =
+            (&action, self.queue.back_mut())
+        {
+            *queued = action;
+            self.metrics.coalesced += 1;
+            return;
+        }
+        self.queue.push_back(action);
+    }
+
+    /// Log the depth of the queue to the log buffer
+    fn report(&mut self) {
+        let depth = self.queue.len();
+        let metrics = &mut self.metrics;
+        metrics.max_depth = std::cmp::max(metrics.max_depth, depth);
+        if metrics.reported.elapsed() < METRICS_INTERVAL {
+            return;
+        }
+        if metrics.max_depth > HIGH_WATER {
+            log::warn!("Action bus is falling behind, {} actions were waiting", metrics.max_depth);
+        }
+        log::debug!(
+            "Action bus depth {} (max {}), {} received, {} coalesced",
+            depth,
+            metrics.max_depth,
+            metrics.received,
+            metrics.coalesced
+        );
+        *metrics =
+            Metrics { received: 0, coalesced: 0, max_depth: depth, reported: Instant::now() };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn consecutive_resizes_are_coalesced_in_order() {
+        let (tx, mut rx) = channel();
+        let sent = [
+            Action::Resize(10, 10),
+            Action::Resize(20, 20),
+            Action::ToggleNicklist,
+            Action::Resize(30, 30),
+            Action::FocusLost,
+            Action::Resize(40, 40),
+            Action::Resize(50, 50),
+            Action::Quit,
+        ];
+        sent.into_iter().for_each(|action| tx.send(action).unwrap());
+        drop(tx);
+
+        let mut received = Vec::new();
+        while let Some(action) = rx.recv().await {
+            received.push(action);
+        }
+        assert_eq!(
+            received,
+            vec![
+                Action::Resize(20, 20),
+                Action::ToggleNicklist,
+                Action::Resize(30, 30),
+                Action::FocusLost,
+                Action::Resize(50, 50),
+                Action::Quit,
+            ]
+        );
+        assert_eq!(rx.metrics.coalesced, 2);
+    }
+}
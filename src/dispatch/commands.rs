@@ -1,36 +1,62 @@
 //! Commands events which may manipulate the state of the terminal
+mod aliases;
+mod registry;
+
 use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
 use tokio::{
-    sync::{
-        broadcast::Sender as BroadcastSender,
-        mpsc::{Receiver, Sender},
-    },
+    sync::mpsc::{Receiver, Sender},
     task::JoinHandle,
 };
 
+use self::{aliases::Aliases, registry::COMMANDS};
 use crate::{
-    dispatch::{Action, XMTPAction},
-    types::Group,
+    dispatch::{Action, ActionSender, XMTPAction},
+    types::{AppError, ErrorSource, Group},
+    util::{get_config_dir, set_log_filter, ALIASES_FILE},
 };
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CommandAction {
-    Help,
-    /// Register a new identity with XMTP
+    /// List the commands, or show how to use the named one
+    Help(Option<String>),
+    /// Register the installation of the current wallet with XMTP
     Register,
-    /// Generate a new ephemeral wallet identity
+    /// Switch to a newly generated ephemeral wallet
     Generate,
     /// Create a new group
-    Create,
-    /// Join a group
-    Join,
+    Create {
+        name: Option<String>,
+        description: Option<String>,
+    },
+    /// Join a group we were invited to, by id or name
+    Join(String),
+    Invites(InvitesCommand),
     /// Invite to a group
     Invite(Group, String),
     /// Information about you (Wallet Address, ENS Profile, etc.)
     Me,
+    /// Edit a message in `$EDITOR`, starting with the given text
+    Compose(String),
+    /// Start or stop capturing the mouse
+    Mouse,
+    /// Jump to a buffer by number or name
+    Buffer(String),
+    /// Show or hide the nicklist
+    Nicklist,
+    /// Split or close windows
+    Window(WindowCommand),
+    /// Change which records are shown in the log buffer
+    Debug(String),
+    /// Send a message to the buffer with this number or name
+    Message(String, String),
+    /// Manage the commands defined by the user
+    Alias(AliasCommand),
     Quit,
     List(ListCommand),
-    Unknown(String),
+    /// A command which is not built in, with the line it was typed as and the buffer it was typed
+    /// in. It may be an alias.
+    Unknown(Group, String),
 }
 
 impl From<CommandAction> for Action {
@@ -39,7 +65,7 @@ impl From<CommandAction> for Action {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ListCommand {
     Group,
     Users,
@@ -51,65 +77,101 @@ impl From<ListCommand> for CommandAction {
     }
 }
 
+/// Groups we were welcomed into. Accepting joins them, declining denies consent to them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InvitesCommand {
+    List,
+    Accept(String),
+    Decline(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AliasCommand {
+    List,
+    /// Show what an alias expands into
+    Show(String),
+    /// Define an alias as (name, expansion)
+    Set(String, String),
+    Remove(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowCommand {
+    /// Split the focused window into one above the other
+    SplitHorizontal,
+    /// Split the focused window into two side by side
+    SplitVertical,
+    /// Close the focused window
+    Close,
+    /// Focus the next window
+    Next,
+}
+
+impl From<WindowCommand> for Action {
+    fn from(window: WindowCommand) -> Action {
+        Action::Window(window)
+    }
+}
+
 pub struct Commands {
-    tx: BroadcastSender<Action>,
+    tx: ActionSender,
     xmtp: Sender<XMTPAction>,
     commands: Receiver<CommandAction>,
+    aliases: Aliases,
 }
 
 impl CommandAction {
-    /// Return a help message for these commands
-    fn help() -> String {
+    /// Names of every command, as typed after the `/`
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        COMMANDS.iter().map(|command| command.name)
+    }
+
+    /// Fixed values the first argument of `command` may take
+    pub fn arguments(command: &str) -> &'static [&'static str] {
+        match registry::find(command).and_then(|command| command.args.first()) {
+            Some(arg) => arg.values,
+            None => &[],
+        }
+    }
+
+    /// Flags `command` takes, i.e `--name`
+    pub fn flags(command: &str) -> Vec<String> {
+        registry::find(command).map(|command| command.flag_names()).unwrap_or_default()
+    }
+
+    /// Return a help message for these commands, or how to use `command`
+    fn help(command: Option<&str>) -> String {
+        if let Some(command) = command.and_then(registry::find) {
+            return command.help();
+        }
         let mut msg = String::from("xChat Help Message");
-        msg.push_str("\n    /help: Receive this help dialogue");
-        msg.push_str("\n    /quit: quit the app");
-        msg.push_str("\n    /register: register this instance with XMTP");
-        msg.push_str("\n    /list {groups|users}: list the users or groups you are apart of");
-        msg.push_str("\n    /generate: generate a new ephemeral wallet identity");
-        msg.push_str("\n    /create: create a new group");
-        msg.push_str("\n    /join {group_id}: join a group");
-        msg.push_str("\n    /invite {user_id}: invite to join a group");
+        for command in COMMANDS {
+            msg.push_str(&format!("\n    {}: {}", command.usage(), command.summary));
+            if let Some(keys) = command.keys {
+                msg.push_str(&format!(" (also {})", keys));
+            }
+        }
         msg.push_str(
-            "\n    /me: get information about the current sessions wallet address, balance, network, etc. ",
+            "\n    Alt-g, Alt-i, Alt-s: group info, identity and settings pages (Esc to go back)",
         );
+        msg.push_str("\nUse `/help <command>` for the aliases and flags of a command");
         msg
     }
 
+    /// Parse a command typed without its leading `/`
     pub fn from_string(command: String, group: &Group) -> Result<Self> {
-        let command = command.split(" ").collect::<Vec<_>>();
-        let cmd = match command[0] {
-            "help" => CommandAction::Help,
-            "quit" => CommandAction::Quit,
-            "register" => CommandAction::Register,
-            "list" => CommandAction::List(ListCommand::Users),
-            "generate" => CommandAction::Generate,
-            "create" => CommandAction::Create,
-            "join" => CommandAction::Join,
-            "invite" => {
-                if command.get(1).is_some() {
-                    CommandAction::Invite(group.clone(), command[1].into())
-                } else {
-                    bail!("`/invite` requires indicating the wallet address of the user to invite");
-                }
-            }
-            "me" => CommandAction::Me,
-            s => CommandAction::Unknown(s.into()),
-        };
-        Ok(cmd)
-    }
-
-    fn noop() -> String {
-        "this command currently does nothing, but will in the future".into()
+        registry::parse(&command, group)
     }
 }
 
 impl Commands {
     pub fn new(
-        tx: BroadcastSender<Action>,
+        tx: ActionSender,
         xmtp: Sender<XMTPAction>,
         commands: Receiver<CommandAction>,
     ) -> Self {
-        Self { tx, xmtp, commands }
+        let aliases = Aliases::load(get_config_dir().join(ALIASES_FILE.clone()));
+        Self { tx, xmtp, commands, aliases }
     }
 
     pub fn spawn(self) -> JoinHandle<()> {
@@ -123,31 +185,94 @@ impl Commands {
 
     async fn event_loop(mut self) -> Result<()> {
         while let Some(event) = self.commands.recv().await {
-            match event {
-                CommandAction::Help => self.send_message(CommandAction::help()).map(|_| ())?,
-                CommandAction::Quit => self.tx.send(Action::Quit).map(|_| ())?,
-                CommandAction::Register => self.send_message(CommandAction::noop()).map(|_| ())?,
-                CommandAction::Generate => self.send_message(CommandAction::noop()).map(|_| ())?,
-                CommandAction::List(_) => self.send_message(CommandAction::noop()).map(|_| ())?,
-                CommandAction::Create => {
-                    log::debug!("Sent CreateGroup XMTP Action");
-                    self.xmtp.send(XMTPAction::CreateGroup).await?;
-                }
-                CommandAction::Join => self.send_message(CommandAction::noop()).map(|_| ())?,
-                CommandAction::Invite(group, user) => {
-                    log::debug!("Inviting to group");
-                    self.xmtp.send(XMTPAction::Invite(group, user)).await?;
+            let group = match &event {
+                CommandAction::Invite(group, _) | CommandAction::Unknown(group, _) => {
+                    group.id.clone()
                 }
-                CommandAction::Me => self.xmtp.send(XMTPAction::Info).await?,
-                CommandAction::Unknown(s) => self.send_message(format!(
-                    "Unknown command: /{}. use `/help` to get a list of commands",
-                    s
-                ))?,
+                _ => vec![0],
             };
+            if let Err(e) = self.handle_command(event).await {
+                self.tx.send(Action::Error(AppError::new(ErrorSource::Command, group, e)))?;
+            }
         }
         Ok(())
     }
 
+    async fn handle_command(&mut self, event: CommandAction) -> Result<()> {
+        match event {
+            CommandAction::Help(command) => {
+                self.send_message(CommandAction::help(command.as_deref()))?
+            }
+            CommandAction::Quit => self.tx.send(Action::Quit).map(|_| ())?,
+            CommandAction::Register => self.xmtp.send(XMTPAction::Register).await?,
+            CommandAction::Generate => self.xmtp.send(XMTPAction::Generate).await?,
+            CommandAction::List(list) => self.tx.send(Action::List(list)).map(|_| ())?,
+            CommandAction::Create { name, description } => {
+                log::debug!("Sent CreateGroup XMTP Action");
+                self.xmtp.send(XMTPAction::CreateGroup { name, description }).await?;
+            }
+            CommandAction::Join(group) | CommandAction::Invites(InvitesCommand::Accept(group)) => {
+                self.xmtp.send(XMTPAction::Join(group)).await?
+            }
+            CommandAction::Invites(InvitesCommand::List) => {
+                self.xmtp.send(XMTPAction::Invites).await?
+            }
+            CommandAction::Invites(InvitesCommand::Decline(group)) => {
+                self.xmtp.send(XMTPAction::Decline(group)).await?
+            }
+            CommandAction::Invite(group, user) => {
+                log::debug!("Inviting to group");
+                self.xmtp.send(XMTPAction::Invite(group, user)).await?;
+            }
+            CommandAction::Me => self.xmtp.send(XMTPAction::Info).await?,
+            // the editor is run by the input box, which owns the text being composed
+            CommandAction::Compose(_) => (),
+            CommandAction::Mouse => self.tx.send(Action::ToggleMouse).map(|_| ())?,
+            CommandAction::Buffer(buffer) => {
+                let action = match buffer.parse::<usize>() {
+                    Ok(number) => Action::ChangeRoom(number.saturating_sub(1)),
+                    Err(_) => Action::ChangeRoomByName(buffer),
+                };
+                self.tx.send(action).map(|_| ())?
+            }
+            CommandAction::Nicklist => self.tx.send(Action::ToggleNicklist).map(|_| ())?,
+            CommandAction::Window(window) => self.tx.send(window.into()).map(|_| ())?,
+            CommandAction::Debug(level) => {
+                set_log_filter(&level)?;
+                self.send_message(format!("Showing `{}` logs in the log buffer", level))?
+            }
+            CommandAction::Message(buffer, text) => {
+                self.tx.send(Action::SendToBuffer(buffer, text)).map(|_| ())?
+            }
+            CommandAction::Alias(alias) => self.handle_alias(alias)?,
+            // the commands of an alias run one after the other, stopping at the first that fails
+            CommandAction::Unknown(group, line) => {
+                for action in self.aliases.expand(&group, &line)? {
+                    Box::pin(self.handle_command(action)).await?;
+                }
+            }
+        };
+        Ok(())
+    }
+
+    fn handle_alias(&mut self, alias: AliasCommand) -> Result<()> {
+        match alias {
+            AliasCommand::List => self.send_message(self.aliases.list()),
+            AliasCommand::Show(name) => match self.aliases.get(&name) {
+                Some(expansion) => self.send_message(format!("/{}: {}", name, expansion)),
+                None => bail!("There is no alias /{}", name),
+            },
+            AliasCommand::Set(name, expansion) => {
+                self.aliases.set(&name, &expansion)?;
+                self.send_message(format!("/{} now runs `{}`", name, expansion))
+            }
+            AliasCommand::Remove(name) => {
+                self.aliases.remove(&name)?;
+                self.send_message(format!("Removed /{}", name))
+            }
+        }
+    }
+
     pub fn send_message(&mut self, msg: String) -> Result<()> {
         self.tx.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)))?;
         Ok(())
@@ -0,0 +1,219 @@
+//! Commands defined by the user with `/alias-cmd`.
+//! An alias expands into one or more commands separated by `;` outside of quotes, in which `$1` to
+//! `$9` are replaced by the arguments the alias was called with, quoted if needed, and `$*` by all
+//! of them as typed. Aliases are kept in a file
+//! in the config directory, one `name expansion` per line, which may also be edited by hand.
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+
+use super::{registry, CommandAction};
+use crate::types::Group;
+
+/// How many aliases deep an alias may expand, to stop aliases expanding into themselves
+const MAX_DEPTH: usize = 10;
+
+#[derive(Debug, Clone, Default)]
+pub struct Aliases {
+    aliases: BTreeMap<String, String>,
+    path: Option<PathBuf>,
+}
+
+impl Aliases {
+    /// Load the aliases defined in `path`. Aliases defined after this are saved to the same file.
+    pub fn load(path: PathBuf) -> Self {
+        let mut aliases = Self::default();
+        if let Ok(file) = std::fs::read_to_string(&path) {
+            for line in file.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let (name, expansion) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+                if let Err(e) = aliases.check(name, expansion) {
+                    log::warn!("Skipping alias {} in {}: {}", name, path.display(), e);
+                    continue;
+                }
+                aliases.aliases.insert(name.into(), expansion.trim().into());
+            }
+        }
+        aliases.path = Some(path);
+        aliases
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let mut file = String::new();
+        for (name, expansion) in &self.aliases {
+            file.push_str(&format!("{} {}\n", name, expansion));
+        }
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        std::fs::write(path, file)?;
+        Ok(())
+    }
+
+    fn check(&self, name: &str, expansion: &str) -> Result<()> {
+        if name.is_empty() || name.contains(char::is_whitespace) || name.starts_with('/') {
+            bail!("Invalid alias name `{}`", name);
+        }
+        if registry::find(name).is_some() {
+            bail!("/{} is already a command", name);
+        }
+        if expansion.trim().is_empty() {
+            bail!("/{} does not expand into anything", name);
+        }
+        Ok(())
+    }
+
+    pub fn set(&mut self, name: &str, expansion: &str) -> Result<()> {
+        self.check(name, expansion)?;
+        self.aliases.insert(name.into(), expansion.trim().into());
+        self.save()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<()> {
+        if self.aliases.remove(name).is_none() {
+            bail!("There is no alias /{}", name);
+        }
+        self.save()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    /// Every alias, one per line
+    pub fn list(&self) -> String {
+        if self.aliases.is_empty() {
+            return "No aliases, define one with `/alias-cmd name expansion`".into();
+        }
+        let mut list = String::from("Aliases");
+        for (name, expansion) in &self.aliases {
+            list.push_str(&format!("\n    /{}: {}", name, expansion));
+        }
+        list
+    }
+
+    /// The commands run by `line`, which starts with the name of an alias. `group` is the buffer
+    /// it was typed in.
+    pub fn expand(&self, group: &Group, line: &str) -> Result<Vec<CommandAction>> {
+        let mut commands = Vec::new();
+        self.expand_into(group, line, 0, &mut commands)?;
+        Ok(commands)
+    }
+
+    fn expand_into(
+        &self,
+        group: &Group,
+        line: &str,
+        depth: usize,
+        commands: &mut Vec<CommandAction>,
+    ) -> Result<()> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let Some(expansion) = self.get(name) else {
+            bail!("Unknown command: /{}. use `/help` to get a list of commands", name);
+        };
+        if depth == MAX_DEPTH {
+            bail!("/{} expands into too many aliases", name);
+        }
+        let args = registry::words(rest)?;
+        let expansion = registry::commands(expansion)?;
+        for command in expansion.into_iter().map(str::trim).filter(|c| !c.is_empty()) {
+            let command =
+                substitute(command, &args, rest.trim()).map_err(|e| anyhow!("/{}: {}", name, e))?;
+            let command = command.strip_prefix('/').unwrap_or(&command);
+            match CommandAction::from_string(command.into(), group)? {
+                CommandAction::Unknown(_, line) => {
+                    self.expand_into(group, &line, depth + 1, commands)?
+                }
+                action => commands.push(action),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replace `$1` to `$9` with the arguments of an alias, `$*` with all of them and `$$` with `$`.
+/// Arguments are quoted, so one typed in quotes stays one argument.
+fn substitute(command: &str, args: &[String], all: &str) -> Result<String> {
+    let mut substituted = String::new();
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            substituted.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('*') => substituted.push_str(all),
+            Some('$') => substituted.push('$'),
+            Some(n @ '1'..='9') => {
+                let index = n as usize - '1' as usize;
+                let Some(arg) = args.get(index) else {
+                    bail!("missing argument ${}", n);
+                };
+                substituted.push_str(&registry::quote(arg));
+            }
+            Some(other) => {
+                substituted.push('$');
+                substituted.push(other);
+            }
+            None => substituted.push('$'),
+        }
+    }
+    Ok(substituted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatch::ListCommand;
+
+    fn expand(aliases: &[(&str, &str)], line: &str) -> Result<Vec<CommandAction>> {
+        let mut all = Aliases::default();
+        for (name, expansion) in aliases {
+            all.set(name, expansion).unwrap();
+        }
+        all.expand(&Group::new_fake(0), line)
+    }
+
+    #[test]
+    fn splits_on_semicolons_outside_of_quotes() {
+        let commands = expand(&[("greet", r#"/msg 1 "hi; all"; /ls groups"#)], "greet").unwrap();
+        assert_eq!(
+            commands,
+            [
+                CommandAction::Message("1".into(), r#""hi; all""#.into()),
+                CommandAction::List(ListCommand::Group)
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_quoted_arguments_together() {
+        let commands = expand(&[("j", "/join $1")], r#"j "team chat""#).unwrap();
+        assert_eq!(commands, [CommandAction::Join("team chat".into())]);
+        let commands = expand(&[("j", "/join $1")], r#"j 'say "hi"'"#).unwrap();
+        assert_eq!(commands, [CommandAction::Join(r#"say "hi""#.into())]);
+    }
+
+    #[test]
+    fn substitutes_every_argument() {
+        let commands = expand(&[("m", "/msg $1 $*; /msg 2 $$1")], "m 3 hello there").unwrap();
+        assert_eq!(
+            commands,
+            [
+                CommandAction::Message("3".into(), "3 hello there".into()),
+                CommandAction::Message("2".into(), "$1".into())
+            ]
+        );
+        assert!(expand(&[("j", "/join $2")], "j team").is_err());
+    }
+
+    #[test]
+    fn stops_aliases_expanding_into_themselves() {
+        let error = expand(&[("ping", "/pong"), ("pong", "/ping")], "ping").unwrap_err();
+        assert!(error.to_string().contains("too many aliases"));
+    }
+}
@@ -0,0 +1,701 @@
+//! Every `/` command, with its arguments and flags.
+//! Typed commands are parsed and validated against this table, and `/help` and the tab completion
+//! of the input box are generated from it.
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+
+use super::{AliasCommand, CommandAction, InvitesCommand, ListCommand, WindowCommand};
+use crate::types::Group;
+
+/// A positional argument of a command
+pub struct Arg {
+    pub name: &'static str,
+    pub required: bool,
+    /// Takes the rest of the line as typed, quotes and spaces included
+    pub rest: bool,
+    /// Values completed for the argument
+    pub values: &'static [&'static str],
+    /// Only the `values` are accepted
+    pub strict: bool,
+}
+
+impl Arg {
+    const fn new(name: &'static str) -> Self {
+        Self { name, required: true, rest: false, values: &[], strict: false }
+    }
+
+    const fn optional(self) -> Self {
+        Self { required: false, ..self }
+    }
+
+    const fn rest(self) -> Self {
+        Self { rest: true, ..self }
+    }
+
+    const fn one_of(self, values: &'static [&'static str]) -> Self {
+        Self { values, strict: true, ..self }
+    }
+
+    const fn suggest(self, values: &'static [&'static str]) -> Self {
+        Self { values, ..self }
+    }
+
+    fn usage(&self) -> String {
+        let name = if self.strict { self.values.join("|") } else { self.name.to_string() };
+        let name = if self.rest { format!("{}...", name) } else { name };
+        match (self.strict, self.required) {
+            (true, _) => format!("{{{}}}", name),
+            (false, true) => format!("<{}>", name),
+            (false, false) => format!("[{}]", name),
+        }
+    }
+}
+
+/// A flag given as `--long`, `--long=value`, `-s` or `-s value`
+pub struct Flag {
+    pub long: &'static str,
+    pub short: Option<char>,
+    /// Name of the value, for flags which take one
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+impl Flag {
+    fn usage(&self) -> String {
+        let names = match self.short {
+            Some(short) => format!("-{}, --{}", short, self.long),
+            None => format!("--{}", self.long),
+        };
+        match self.value {
+            Some(value) => format!("{} <{}>", names, value),
+            None => names,
+        }
+    }
+}
+
+/// Arguments and flags of a parsed command
+#[derive(Default)]
+pub struct Args {
+    positional: Vec<String>,
+    flags: HashMap<&'static str, Option<String>>,
+    /// The command they were parsed for, to explain how to use it
+    command: Option<&'static Command>,
+}
+
+impl Args {
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.positional.get(index).map(String::as_str)
+    }
+
+    /// An argument the command requires. Parsing checks they are given, unless the command
+    /// declares the argument as optional.
+    pub fn required(&self, index: usize) -> Result<String> {
+        if let Some(arg) = self.positional.get(index) {
+            return Ok(arg.clone());
+        }
+        match self.command {
+            Some(command) => {
+                let name = match command.args.get(index) {
+                    Some(arg) => arg.usage(),
+                    None => format!("argument {}", index + 1),
+                };
+                command.usage_error(format!("Missing {}", name))
+            }
+            None => bail!("Missing argument {}", index + 1),
+        }
+    }
+
+    /// The value given to the flag `long`
+    pub fn flag(&self, long: &str) -> Option<String> {
+        self.flags.get(long).cloned().flatten()
+    }
+
+    /// Whether the flag `long` was given
+    pub fn has(&self, long: &str) -> bool {
+        self.flags.contains_key(long)
+    }
+}
+
+pub struct Command {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub args: &'static [Arg],
+    pub flags: &'static [Flag],
+    pub summary: &'static str,
+    /// Keys doing the same as the command
+    pub keys: Option<&'static str>,
+    build: fn(Args, &Group) -> Result<CommandAction>,
+}
+
+pub static COMMANDS: &[Command] = &[
+    Command {
+        name: "help",
+        aliases: &["h", "?"],
+        args: &[Arg::new("command").optional()],
+        flags: &[],
+        summary: "list the commands, or show how to use one",
+        keys: None,
+        build: |args, _| {
+            let command = match args.get(0) {
+                Some(name) => Some(find(name).ok_or(anyhow!("No command named /{}", name))?),
+                None => None,
+            };
+            Ok(CommandAction::Help(command.map(|c| c.name.to_string())))
+        },
+    },
+    Command {
+        name: "quit",
+        aliases: &["exit", "q"],
+        args: &[],
+        flags: &[],
+        summary: "quit the app",
+        keys: None,
+        build: |_, _| Ok(CommandAction::Quit),
+    },
+    Command {
+        name: "register",
+        aliases: &[],
+        args: &[],
+        flags: &[],
+        summary: "register the wallet with XMTP, needed to join groups and send messages",
+        keys: None,
+        build: |_, _| Ok(CommandAction::Register),
+    },
+    Command {
+        name: "list",
+        aliases: &["ls"],
+        args: &[Arg::new("kind").one_of(&["groups", "users"])],
+        flags: &[],
+        summary: "list the groups you are in, or the users in them",
+        keys: None,
+        build: |args, _| match args.get(0) {
+            Some("groups") => Ok(ListCommand::Group.into()),
+            _ => Ok(ListCommand::Users.into()),
+        },
+    },
+    Command {
+        name: "generate",
+        aliases: &[],
+        args: &[],
+        flags: &[],
+        summary: "switch to a newly generated ephemeral wallet",
+        keys: None,
+        build: |_, _| Ok(CommandAction::Generate),
+    },
+    Command {
+        name: "create",
+        aliases: &["new"],
+        args: &[],
+        flags: &[
+            Flag { long: "name", short: Some('n'), value: Some("name"), help: "name of the group" },
+            Flag {
+                long: "description",
+                short: Some('d'),
+                value: Some("text"),
+                help: "what the group is about",
+            },
+        ],
+        summary: "create a new group",
+        keys: None,
+        build: |args, _| {
+            Ok(CommandAction::Create {
+                name: args.flag("name"),
+                description: args.flag("description"),
+            })
+        },
+    },
+    Command {
+        name: "join",
+        aliases: &[],
+        args: &[Arg::new("group_id|name")],
+        flags: &[],
+        summary: "join a group you were invited to",
+        keys: None,
+        build: |args, _| Ok(CommandAction::Join(args.required(0)?)),
+    },
+    Command {
+        name: "invites",
+        aliases: &[],
+        args: &[
+            Arg::new("action").optional().one_of(&["accept", "decline"]),
+            Arg::new("group_id|name").optional(),
+        ],
+        flags: &[],
+        summary: "list the groups you were invited to, or accept or decline one",
+        keys: None,
+        build: |args, _| {
+            let invites = match (args.get(0), args.get(1)) {
+                (None, _) => InvitesCommand::List,
+                (Some(_), None) => bail!("Missing the group to accept or decline"),
+                (Some("accept"), Some(group)) => InvitesCommand::Accept(group.into()),
+                (Some(_), Some(group)) => InvitesCommand::Decline(group.into()),
+            };
+            Ok(CommandAction::Invites(invites))
+        },
+    },
+    Command {
+        name: "invite",
+        aliases: &[],
+        args: &[Arg::new("address")],
+        flags: &[],
+        summary: "invite a wallet address to join the focused group",
+        keys: None,
+        build: |args, group| Ok(CommandAction::Invite(group.clone(), args.required(0)?)),
+    },
+    Command {
+        name: "me",
+        aliases: &["whoami"],
+        args: &[],
+        flags: &[],
+        summary: "get information about the current session's wallet address, database, etc.",
+        keys: None,
+        build: |_, _| Ok(CommandAction::Me),
+    },
+    Command {
+        name: "compose",
+        aliases: &[],
+        args: &[Arg::new("text").optional().rest()],
+        flags: &[],
+        summary: "write a message in $EDITOR",
+        keys: Some("Alt-e"),
+        build: |args, _| Ok(CommandAction::Compose(args.get(0).unwrap_or_default().into())),
+    },
+    Command {
+        name: "mouse",
+        aliases: &[],
+        args: &[],
+        flags: &[],
+        summary: "toggle mouse support, allowing the terminal to select text",
+        keys: None,
+        build: |_, _| Ok(CommandAction::Mouse),
+    },
+    Command {
+        name: "buffer",
+        aliases: &["b"],
+        args: &[Arg::new("number|name")],
+        flags: &[],
+        summary: "jump to a buffer",
+        keys: Some("Alt-1..9, Alt-a for activity"),
+        build: |args, _| Ok(CommandAction::Buffer(args.required(0)?)),
+    },
+    Command {
+        name: "nicklist",
+        aliases: &[],
+        args: &[],
+        flags: &[],
+        summary: "show or hide the members of the buffer",
+        keys: Some("Alt-n"),
+        build: |_, _| Ok(CommandAction::Nicklist),
+    },
+    Command {
+        name: "window",
+        aliases: &["w"],
+        args: &[Arg::new("command").one_of(&["splith", "splitv", "close", "next"])],
+        flags: &[],
+        summary: "split, close or cycle windows",
+        keys: Some("Alt-w"),
+        build: |args, _| {
+            let window = match args.get(0) {
+                Some("splith") => WindowCommand::SplitHorizontal,
+                Some("splitv") => WindowCommand::SplitVertical,
+                Some("close") => WindowCommand::Close,
+                _ => WindowCommand::Next,
+            };
+            Ok(CommandAction::Window(window))
+        },
+    },
+    Command {
+        name: "msg",
+        aliases: &[],
+        args: &[Arg::new("number|name"), Arg::new("text").rest()],
+        flags: &[],
+        summary: "send a message to a buffer without focusing it",
+        keys: None,
+        build: |args, _| Ok(CommandAction::Message(args.required(0)?, args.required(1)?)),
+    },
+    Command {
+        name: "alias-cmd",
+        aliases: &[],
+        args: &[Arg::new("name").optional(), Arg::new("expansion").optional().rest()],
+        flags: &[Flag { long: "remove", short: Some('r'), value: None, help: "remove the alias" }],
+        summary: "list, show or define aliases, i.e `/alias-cmd j /join $1`",
+        keys: None,
+        build: |args, _| {
+            let alias = match (args.get(0), args.get(1)) {
+                (None, _) => AliasCommand::List,
+                (Some(name), _) if args.has("remove") => AliasCommand::Remove(name.into()),
+                (Some(name), None) => AliasCommand::Show(name.into()),
+                (Some(name), Some(expansion)) => AliasCommand::Set(name.into(), expansion.into()),
+            };
+            Ok(CommandAction::Alias(alias))
+        },
+    },
+    Command {
+        name: "debug",
+        aliases: &[],
+        args: &[Arg::new("level").rest().suggest(&["error", "warn", "info", "debug", "trace"])],
+        flags: &[],
+        summary: "show logs at this level in the log buffer, i.e `debug` or `xchat=trace`",
+        keys: None,
+        build: |args, _| Ok(CommandAction::Debug(args.required(0)?)),
+    },
+];
+
+/// The command called `name`, or with `name` as an alias
+pub fn find(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|c| c.name == name || c.aliases.contains(&name))
+}
+
+/// Parse a command typed without its leading `/`. Names which are not commands are parsed as
+/// [`CommandAction::Unknown`], as they may be aliases.
+pub fn parse(line: &str, group: &Group) -> Result<CommandAction> {
+    let mut words = Words::new(line);
+    let Some(name) = words.next()? else {
+        bail!("Type a command after the `/`, i.e `/help`");
+    };
+    match find(&name) {
+        Some(command) => (command.build)(command.parse(&mut words)?, group),
+        None => Ok(CommandAction::Unknown(group.clone(), line.trim().into())),
+    }
+}
+
+/// Split `line` into words the way command arguments are
+pub fn words(line: &str) -> Result<Vec<String>> {
+    let mut words = Words::new(line);
+    let mut all = Vec::new();
+    while let Some(word) = words.next()? {
+        all.push(word);
+    }
+    Ok(all)
+}
+
+/// Split `line` on the `;` outside of quotes, keeping each command as typed
+pub fn commands(line: &str) -> Result<Vec<&str>> {
+    let mut commands = Vec::new();
+    let (mut start, mut quote, mut escaped) = (0, None, false);
+    // whether the next character starts a word, or the value of a `--flag=`
+    let mut word_start = true;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else {
+            match (quote, c) {
+                (_, '\\') => escaped = true,
+                (None, '"' | '\'') if word_start => quote = Some(c),
+                (Some(q), c) if q == c => quote = None,
+                (None, ';') => {
+                    commands.push(&line[start..i]);
+                    start = i + c.len_utf8();
+                }
+                _ => (),
+            }
+        }
+        word_start = quote.is_none() && !escaped && (c.is_whitespace() || c == ';' || c == '=');
+    }
+    if let Some(quote) = quote {
+        bail!("Missing the closing {} of a quoted argument", quote);
+    }
+    commands.push(&line[start..]);
+    Ok(commands)
+}
+
+/// `word` quoted if it needs to be, to be parsed back as one word
+pub fn quote(word: &str) -> String {
+    let special = |c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | ';');
+    if !word.is_empty() && !word.contains(special) {
+        return word.into();
+    }
+    format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Command {
+    pub fn usage(&self) -> String {
+        let mut usage = format!("/{}", self.name);
+        for flag in self.flags {
+            usage.push_str(&format!(" [{}]", flag.usage()));
+        }
+        for arg in self.args {
+            usage.push(' ');
+            usage.push_str(&arg.usage());
+        }
+        usage
+    }
+
+    /// What `/help` says about this command
+    pub fn help(&self) -> String {
+        let mut help = format!("{}\n    {}", self.usage(), self.summary);
+        if !self.aliases.is_empty() {
+            let aliases = self.aliases.iter().map(|a| format!("/{}", a)).collect::<Vec<_>>();
+            help.push_str(&format!("\n    aliases: {}", aliases.join(", ")));
+        }
+        if let Some(keys) = self.keys {
+            help.push_str(&format!("\n    keys: {}", keys));
+        }
+        for flag in self.flags {
+            help.push_str(&format!("\n    {}: {}", flag.usage(), flag.help));
+        }
+        help
+    }
+
+    /// Every spelling of the flags, for completion
+    pub fn flag_names(&self) -> Vec<String> {
+        let long = self.flags.iter().map(|f| format!("--{}", f.long));
+        let short = self.flags.iter().filter_map(|f| f.short).map(|s| format!("-{}", s));
+        long.chain(short).collect()
+    }
+
+    fn parse(&'static self, words: &mut Words) -> Result<Args> {
+        let mut args = Args { command: Some(self), ..Default::default() };
+        let mut flags_ended = false;
+        loop {
+            if self.args.get(args.positional.len()).is_some_and(|arg| arg.rest) {
+                let rest = words.rest();
+                if !rest.is_empty() {
+                    args.positional.push(rest.to_string());
+                }
+                break;
+            }
+            let Some(word) = words.next()? else { break };
+            match word.strip_prefix('-') {
+                Some("-") if !flags_ended => flags_ended = true,
+                Some(flag) if !flags_ended && !flag.is_empty() => {
+                    self.parse_flag(flag, words, &mut args)?
+                }
+                _ => args.positional.push(word),
+            }
+        }
+        self.validate(&args)?;
+        Ok(args)
+    }
+
+    /// Parse a flag without its first `-`
+    fn parse_flag(&self, flag: &str, words: &mut Words, args: &mut Args) -> Result<()> {
+        let (spec, value) = match flag.strip_prefix('-') {
+            Some(long) => {
+                let (name, value) = match long.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (long, None),
+                };
+                (self.flags.iter().find(|f| f.long == name), value)
+            }
+            None => {
+                let mut chars = flag.chars();
+                let short = match (chars.next(), chars.next()) {
+                    (Some(short), None) => Some(short),
+                    _ => None,
+                };
+                (self.flags.iter().find(|f| f.short.is_some() && f.short == short), None)
+            }
+        };
+        let Some(spec) = spec else {
+            return self.usage_error(format!("Unknown flag -{}", flag));
+        };
+        let value = match (spec.value, value) {
+            (Some(_), Some(value)) => Some(value),
+            (Some(name), None) => match words.next()? {
+                Some(value) => Some(value),
+                None => return self.usage_error(format!("--{} needs a <{}>", spec.long, name)),
+            },
+            (None, Some(_)) => {
+                return self.usage_error(format!("--{} does not take a value", spec.long))
+            }
+            (None, None) => None,
+        };
+        args.flags.insert(spec.long, value);
+        Ok(())
+    }
+
+    fn validate(&self, args: &Args) -> Result<()> {
+        if let Some(missing) = self.args.iter().filter(|a| a.required).nth(args.positional.len()) {
+            return self.usage_error(format!("Missing {}", missing.usage()));
+        }
+        if args.positional.len() > self.args.len() {
+            return self.usage_error("Too many arguments");
+        }
+        for (arg, value) in self.args.iter().zip(&args.positional) {
+            if arg.strict && !arg.values.contains(&value.as_str()) {
+                return self.usage_error(format!("`{}` is not one of {}", value, arg.usage()));
+            }
+        }
+        Ok(())
+    }
+
+    fn usage_error<T>(&self, problem: impl std::fmt::Display) -> Result<T> {
+        bail!("{}. Usage: {}", problem, self.usage())
+    }
+}
+
+/// Splits a command into words, on whitespace outside of quotes. Quotes are only special at the
+/// start of a word or after a `=`, and a backslash escapes the next character.
+struct Words<'a> {
+    line: &'a str,
+    /// Byte offset of the next word
+    position: usize,
+}
+
+impl<'a> Words<'a> {
+    fn new(line: &'a str) -> Self {
+        Self { line, position: 0 }
+    }
+
+    fn next(&mut self) -> Result<Option<String>> {
+        let mut chars = self.line[self.position..].char_indices().peekable();
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            self.position = self.line.len();
+            return Ok(None);
+        }
+
+        let mut word = String::new();
+        let mut quote = None;
+        let mut end = self.line.len() - self.position;
+        // quotes only start an argument or the value of a `--flag=`, so apostrophes inside words
+        // are kept
+        let mut start = true;
+        while let Some((i, c)) = chars.next() {
+            let at_start = std::mem::replace(&mut start, false) || word.ends_with('=');
+            match (quote, c) {
+                (_, '\\') => match chars.next() {
+                    Some((_, escaped)) => word.push(escaped),
+                    None => bail!("Nothing to escape at the end of the command"),
+                },
+                (None, '"' | '\'') if at_start => quote = Some(c),
+                (Some(q), c) if q == c => quote = None,
+                (None, c) if c.is_whitespace() => {
+                    end = i;
+                    break;
+                }
+                (_, c) => word.push(c),
+            }
+        }
+        if let Some(quote) = quote {
+            bail!("Missing the closing {} of a quoted argument", quote);
+        }
+        self.position += end;
+        Ok(Some(word))
+    }
+
+    /// The rest of the line, as typed
+    fn rest(&mut self) -> &'a str {
+        let rest = self.line[self.position..].trim();
+        self.position = self.line.len();
+        rest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_line(line: &str) -> Result<CommandAction> {
+        parse(line, &Group::new_fake(0))
+    }
+
+    fn error(line: &str) -> String {
+        parse_line(line).expect_err("should not parse").to_string()
+    }
+
+    #[test]
+    fn splits_quoted_and_escaped_words() {
+        let line = r#"a "b c" 'd e' f\ g \"h"#;
+        assert_eq!(words(line).unwrap(), ["a", "b c", "d e", "f g", "\"h"]);
+        assert_eq!(words(r#"'it"s' "a'b""#).unwrap(), ["it\"s", "a'b"]);
+        assert_eq!(words("  ").unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn keeps_apostrophes_inside_words() {
+        assert_eq!(parse_line("join Bob's").unwrap(), CommandAction::Join("Bob's".into()));
+        assert_eq!(words("don't stop").unwrap(), ["don't", "stop"]);
+    }
+
+    #[test]
+    fn rejects_unclosed_quotes_and_escapes() {
+        assert!(words("join \"team").unwrap_err().to_string().contains("closing \""));
+        assert!(words("join team\\").unwrap_err().to_string().contains("Nothing to escape"));
+    }
+
+    #[test]
+    fn parses_long_and_short_flags() {
+        let create = CommandAction::Create {
+            name: Some("team chat".into()),
+            description: Some("about it".into()),
+        };
+        assert_eq!(parse_line(r#"create --name="team chat" -d "about it""#).unwrap(), create);
+        assert_eq!(parse_line(r#"new -n "team chat" --description 'about it'"#).unwrap(), create);
+        let alias = CommandAction::Alias(AliasCommand::Remove("j".into()));
+        assert_eq!(parse_line("alias-cmd -r j").unwrap(), alias);
+    }
+
+    #[test]
+    fn rejects_bad_flags() {
+        assert!(error("create --nope").starts_with("Unknown flag --nope"));
+        assert!(error("create -n").starts_with("--name needs a <name>"));
+        assert!(error("alias-cmd --remove=yes j").starts_with("--remove does not take a value"));
+    }
+
+    #[test]
+    fn double_dash_ends_flags() {
+        let alias = CommandAction::Alias(AliasCommand::Show("-r".into()));
+        assert_eq!(parse_line("alias-cmd -- -r").unwrap(), alias);
+        assert_eq!(parse_line("join -- --team").unwrap(), CommandAction::Join("--team".into()));
+    }
+
+    #[test]
+    fn rest_arguments_take_the_line_as_typed() {
+        let message = CommandAction::Message("2".into(), r#"hi "there"  -r 'you'"#.into());
+        assert_eq!(parse_line(r#"msg 2 hi "there"  -r 'you'  "#).unwrap(), message);
+        let alias = CommandAction::Alias(AliasCommand::Set("j".into(), "/join $1; /ls".into()));
+        assert_eq!(parse_line("alias-cmd j /join $1; /ls").unwrap(), alias);
+        assert_eq!(parse_line("compose").unwrap(), CommandAction::Compose(String::new()));
+    }
+
+    #[test]
+    fn rejects_missing_and_extra_arguments() {
+        assert_eq!(error("join"), "Missing <group_id|name>. Usage: /join <group_id|name>");
+        assert!(error("msg 2").starts_with("Missing <text...>"));
+        assert!(error("join a b").starts_with("Too many arguments"));
+        assert!(error("quit now").starts_with("Too many arguments"));
+    }
+
+    #[test]
+    fn only_accepts_strict_values() {
+        assert_eq!(parse_line("list groups").unwrap(), CommandAction::List(ListCommand::Group));
+        assert_eq!(parse_line("ls users").unwrap(), CommandAction::List(ListCommand::Users));
+        assert!(error("list rooms").starts_with("`rooms` is not one of {groups|users}"));
+        assert!(error("window split").contains("is not one of"));
+        // suggested values are not enforced
+        assert_eq!(
+            parse_line("debug xchat=trace").unwrap(),
+            CommandAction::Debug("xchat=trace".into())
+        );
+    }
+
+    #[test]
+    fn optional_arguments_still_required_by_the_command() {
+        assert!(error("invites accept").starts_with("Missing the group to accept or decline"));
+    }
+
+    #[test]
+    fn splits_commands_on_unquoted_semicolons() {
+        let line = r#"msg 1 "a; b"; join c\;d ; x='e;f';"#;
+        assert_eq!(commands(line).unwrap(), [r#"msg 1 "a; b""#, r#" join c\;d "#, " x='e;f'", ""]);
+        assert_eq!(commands("ls; Bob's; join").unwrap(), ["ls", " Bob's", " join"]);
+    }
+
+    #[test]
+    fn quotes_words_to_be_read_back() {
+        for word in ["team", "team chat", "", r#"say "hi""#, "a\\b", "Bob's", "x;y"] {
+            assert_eq!(words(&quote(word)).unwrap(), [word]);
+        }
+        assert_eq!(quote("team"), "team");
+    }
+
+    #[test]
+    fn leaves_unknown_commands_to_aliases() {
+        let unknown = CommandAction::Unknown(Group::new_fake(0), "greet bob".into());
+        assert_eq!(parse_line(" greet bob ").unwrap(), unknown);
+        assert!(error("").starts_with("Type a command"));
+    }
+}
@@ -0,0 +1,157 @@
+//! Recording and replaying of the actions coming into the views, to reproduce bugs in them.
+//! A recording has one line per [`Action`], as JSON with the milliseconds since recording started.
+//! Only what the terminal, XMTP and the commands send is recorded. Replaying feeds those back at
+//! the pace they were recorded, against a backend that does nothing, and the views send what they
+//! derive from them again.
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tokio::{
+    sync::mpsc::{unbounded_channel, Receiver},
+    task::JoinHandle,
+};
+use xmtp_mls::storage::group_message::StoredGroupMessage;
+
+use super::{Action, ActionSender, CommandAction, XMTPAction};
+use crate::types::GroupId;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry<A> {
+    /// Milliseconds since the recording started
+    at: u64,
+    action: A,
+}
+
+/// Writes every action it is given to a file
+pub struct Recorder {
+    file: BufWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Could not create recording {}", path.display()))?;
+        Ok(Self { file: BufWriter::new(file), started: Instant::now() })
+    }
+
+    /// A sender recording every action sent to it, before passing it on to `tx`
+    pub fn tee(mut self, tx: ActionSender) -> ActionSender {
+        let (recorded, mut rx) = unbounded_channel();
+        tokio::spawn(async move {
+            let mut recording = true;
+            while let Some(action) = rx.recv().await {
+                if recording {
+                    if let Err(e) = self.record(&action) {
+                        log::error!("Stopped recording {}", e);
+                        recording = false;
+                    }
+                }
+                if tx.send(action).is_err() {
+                    return;
+                }
+            }
+        });
+        recorded
+    }
+
+    fn record(&mut self, action: &Action) -> Result<()> {
+        let at = self.started.elapsed().as_millis() as u64;
+        serde_json::to_writer(&mut self.file, &Entry { at, action })?;
+        writeln!(self.file)?;
+        // keep everything up to a crash
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Actions read from a recording, with how long after the start they were dispatched
+pub struct Replay {
+    actions: Vec<(Duration, Action)>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Could not open recording {}", path.display()))?;
+        let mut actions = Vec::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let entry: Entry<Action> = serde_json::from_str(&line?)
+                .with_context(|| format!("Invalid action on line {}", number + 1))?;
+            actions.push((Duration::from_millis(entry.at), entry.action));
+        }
+        Ok(Self { actions })
+    }
+
+    /// Send the actions to `tx` at the pace they were recorded
+    pub fn spawn(self, tx: ActionSender) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let started = tokio::time::Instant::now();
+            for (at, action) in self.actions {
+                tokio::time::sleep_until(started + at).await;
+                // the terminal stays with the replay, which ends when the user quits
+                if matches!(
+                    action,
+                    Action::Quit | Action::Suspend | Action::Resume | Action::RunEditor(_)
+                ) {
+                    continue;
+                }
+                if tx.send(action).is_err() {
+                    return;
+                }
+            }
+            let msg = "Replay finished, press q to exit".to_string();
+            let _ = tx.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)));
+        })
+    }
+}
+
+/// Stand-in for the commands and XMTP tasks while replaying. Their actions are only logged, except
+/// for `/quit`.
+pub fn fake_backend(
+    tx: ActionSender,
+    mut xmtp: Receiver<XMTPAction>,
+    mut commands: Receiver<CommandAction>,
+) -> (JoinHandle<()>, JoinHandle<()>) {
+    let commands = tokio::spawn(async move {
+        while let Some(command) = commands.recv().await {
+            log::info!("Replay ignoring command {:?}", command);
+            if command == CommandAction::Quit {
+                let _ = tx.send(Action::Quit);
+            }
+        }
+    });
+    let xmtp = tokio::spawn(async move {
+        while let Some(action) = xmtp.recv().await {
+            log::info!("Replay ignoring XMTP action {:?}", action);
+        }
+    });
+    (commands, xmtp)
+}
+
+/// JSON only has string keys, so messages by group are written as a list of pairs
+pub mod by_group {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        messages: &Arc<HashMap<GroupId, Vec<StoredGroupMessage>>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(messages.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Arc<HashMap<GroupId, Vec<StoredGroupMessage>>>, D::Error> {
+        let pairs = Vec::<(GroupId, Vec<StoredGroupMessage>)>::deserialize(deserializer)?;
+        Ok(Arc::new(pairs.into_iter().collect()))
+    }
+}
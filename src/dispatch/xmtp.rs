@@ -1,28 +1,56 @@
 //! Events to process with libxmtp
 pub mod xmtp_async;
 
-use anyhow::Result;
-use ethers::signers::Signer;
-use tokio::{
-    sync::{broadcast::Sender, mpsc::Receiver},
-    task::JoinHandle,
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
 };
+
+use anyhow::{anyhow, bail, Result};
+use ethers::signers::Signer;
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc::Receiver, task::JoinHandle};
 use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use xmtp_mls::storage::group_message::GroupMessageKind;
 
-use super::Action;
-use crate::{cli::XChatApp, dispatch::xmtp::xmtp_async::AsyncXmtp, types::Group};
+use super::{Action, ActionSender};
+use crate::{
+    cli::XChatApp,
+    dispatch::xmtp::xmtp_async::AsyncXmtp,
+    types::{AppError, ConnectionState, ErrorSource, Group, GroupId, Identity},
+};
 
 /// Actions for XMTP
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum XMTPAction {
     /// Send message to (group_id, message)
     SendMessage(Group, String),
-    CreateGroup,
+    CreateGroup {
+        name: Option<String>,
+        description: Option<String>,
+    },
     /// Invite user to a group
     /// only admin/group creator can do this
     Invite(Group, String),
     /// Send information about the current user
     Info,
+    /// Fetch the members of a group
+    Members(Group),
+    /// Fetch the name, description and permissions of a group
+    GroupInfo(Group),
+    /// Fetch the addresses and installations of our inbox
+    InboxState,
+    /// Join a group we were welcomed into, by its name or id
+    Join(String),
+    /// List the groups we were welcomed into but did not join or decline
+    Invites,
+    /// Decline to join a group we were welcomed into, by its name or id
+    Decline(String),
+    /// Add this installation to the inbox of the wallet, if it is not already
+    Register,
+    /// Switch to a newly generated wallet, starting a new session with it
+    Generate,
 }
 
 impl From<XMTPAction> for Action {
@@ -31,81 +59,384 @@ impl From<XMTPAction> for Action {
     }
 }
 
+/// How long to keep sending the outbox when shutting down
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Messages waiting to be sent, in order. Shared with `main`, so what is left can be printed even
+/// if the XMTP task does not finish in time.
+#[derive(Debug, Clone, Default)]
+pub struct Outbox(Arc<Mutex<VecDeque<(Group, String)>>>);
+
+impl Outbox {
+    fn lock(&self) -> MutexGuard<'_, VecDeque<(Group, String)>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Queue a message, returning how many are waiting
+    fn push(&self, group: Group, message: String) -> usize {
+        let mut outbox = self.lock();
+        outbox.push_back((group, message));
+        outbox.len()
+    }
+
+    /// The next message to send. It stays queued until [`Outbox::pop`], so a message that is
+    /// being sent is still reported if the task is stopped.
+    fn front(&self) -> Option<(Group, String)> {
+        self.lock().front().cloned()
+    }
+
+    /// Take the message that was just sent, returning how many are still waiting
+    fn pop(&self) -> usize {
+        let mut outbox = self.lock();
+        outbox.pop_front();
+        outbox.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Take every message out of the outbox
+    pub fn take(&self) -> Vec<(Group, String)> {
+        self.lock().drain(..).collect()
+    }
+}
+
+/// How a session with one wallet ended
+enum Session {
+    /// Every sender of [`XMTPAction`]s was dropped, what could not be sent is left in the outbox
+    Closed,
+    /// `/generate` asked for a new wallet
+    Generate,
+}
+
 pub struct XMTP {
-    tx: Sender<Action>,
+    tx: ActionSender,
     rx: ReceiverStream<XMTPAction>,
     opts: XChatApp,
+    outbox: Outbox,
 }
 
 impl XMTP {
-    pub fn new(tx: Sender<Action>, rx: Receiver<XMTPAction>, opts: XChatApp) -> Self {
-        Self { tx, rx: ReceiverStream::new(rx), opts }
+    pub fn new(tx: ActionSender, rx: Receiver<XMTPAction>, opts: XChatApp, outbox: Outbox) -> Self {
+        Self { tx, rx: ReceiverStream::new(rx), opts, outbox }
     }
 
-    pub fn spawn(self) -> JoinHandle<()> {
+    /// Run until every sender of [`XMTPAction`]s is dropped. The messages that could not be sent
+    /// are left in the outbox.
+    pub fn spawn(mut self) -> JoinHandle<()> {
         tokio::spawn(async move {
-            match self.event_loop().await {
-                Ok(_) => (),
-                Err(e) => log::error!("error running XMTP Events {}", e),
+            let (tx, network) = (self.tx.clone(), self.opts.network());
+            loop {
+                match self.event_loop().await {
+                    Ok(Session::Closed) => break,
+                    Ok(Session::Generate) => {
+                        let _ = tx.send(Action::Connection(network, ConnectionState::Disconnected));
+                        let _ = tx.send(Action::ClearGroups);
+                    }
+                    Err(e) => {
+                        log::error!("error running XMTP Events {}", e);
+                        break;
+                    }
+                }
             }
+            let _ = tx.send(Action::Connection(network, ConnectionState::Disconnected));
         })
     }
 
-    async fn event_loop(self) -> Result<()> {
+    // the streams borrow `xmtp`, so they are closed before the client is dropped
+    async fn event_loop(&mut self) -> Result<Session> {
         log::info!("Spawning handle");
-        let XMTP { tx, mut rx, opts } = self;
+        let (tx, events, opts, outbox) =
+            (self.tx.clone(), &mut self.rx, &self.opts, self.outbox.clone());
 
+        let network = opts.network();
+        tx.send(Action::Connection(network, ConnectionState::Connecting))?;
+        // every session starts with a new wallet
         let xmtp = AsyncXmtp::new_ephemeral(opts).await?;
+        tx.send(Action::Identity(xmtp.identity()))?;
+        if !xmtp.is_registered() {
+            if let Some(session) = Self::unregistered(&tx, &xmtp, events).await? {
+                return Ok(session);
+            }
+        }
+        tx.send(Action::Connection(network, ConnectionState::Connected))?;
         let messages = xmtp.messages().await?;
         futures::pin_mut!(messages);
         let conversations = xmtp.subscribe_conversations().await?;
         futures::pin_mut!(conversations);
 
-        let events = &mut rx;
         loop {
             tokio::select! {
                 Some(msg) = messages.next() => {
-                    tx.send(Action::ReceiveMessage(msg?))?;
+                    let msg = match msg {
+                        Ok(msg) => msg,
+                        Err(e) => {
+                            Self::send_error(&tx, vec![0], e);
+                            continue;
+                        }
+                    };
+                    let group = Group::new(msg.group_id.clone(), 0, msg.sent_at_ns);
+                    // groups we were only welcomed into, or declined, are not shown
+                    match xmtp.is_joined(&group) {
+                        Ok(true) => (),
+                        Ok(false) => continue,
+                        Err(e) => {
+                            Self::send_error(&tx, vec![0], e);
+                            continue;
+                        }
+                    }
+                    if msg.kind == GroupMessageKind::MembershipChange {
+                        Self::send_members(&tx, &xmtp, group).await;
+                    }
+                    tx.send(Action::ReceiveMessage(Arc::new(msg)))?;
                 },
                 Some(group) = conversations.next() => {
-                    let group = group.unwrap();
-                    log::debug!("Following conversation for group {:?}", group.id);
-                    tx.send(Action::NewGroups(vec![group]))?;
-                },
-                event = events.next() => {
-                    let res: Result<()> = match event {
-                        Some(XMTPAction::SendMessage(group, m)) => {
-                            if group.is_fake() {
-                                tx.send(Action::FakeMessage(group.id, ("xchat".into(), "Invalid Buffer, cannot send MLS messages to this buffer.".into())))?;
-                                continue;
+                    match group {
+                        Ok(group) => {
+                            if let Err(e) = Self::new_group(&tx, &xmtp, group).await {
+                                Self::send_error(&tx, vec![0], e);
                             }
-                            xmtp.send_message(group, m).await
-                        },
-                        Some(XMTPAction::CreateGroup) => {
-                            log::debug!("Creating MLS group");
-                            let group = xmtp.create_group().await?;
-                            tx.send(Action::NewGroups(vec![group]))?;
-                            Ok(())
-                        },
-                        Some(XMTPAction::Invite(group, user)) => {
-                            let user = if !user.starts_with("0x") { format!("0x{}", user) } else { user };
-                            xmtp.invite_user(group, user).await?;
-                            Ok(())
-                        },
-                        Some(XMTPAction::Info) => {
-                            Self::welcome_message(&tx, &xmtp).await
                         }
-                        None => Ok(())
+                        Err(e) => Self::send_error(&tx, vec![0], e),
+                    }
+                },
+                _ = futures::future::ready(()), if outbox.len() > 0 => {
+                    let (group, m) = outbox.front().expect("Checked outbox is not empty");
+                    let id = group.id.clone();
+                    if let Err(e) = xmtp.send_message(group, m).await {
+                        Self::send_error(&tx, id, e);
+                    }
+                    tx.send(Action::Outbox(outbox.pop()))?;
+                },
+                event = events.next() => {
+                    let Some(event) = event else {
+                        log::info!("Shutting down, {} messages left to send", outbox.len());
+                        Self::flush(&xmtp, &outbox).await;
+                        return Ok(Session::Closed);
                     };
-                    if let Err(e) = res {
-                        log::debug!("Action failed to send {}", e);
+                    if event == XMTPAction::Generate {
+                        // the groups of this wallet are left behind, so is what it did not send
+                        Self::flush(&xmtp, &outbox).await;
+                        let unsent = outbox.take();
+                        let mut msg = "Switching to a new wallet".to_string();
+                        if !unsent.is_empty() {
+                            msg.push_str(&format!(", {} messages could not be sent", unsent.len()));
+                        }
+                        tx.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)))?;
+                        return Ok(Session::Generate);
+                    }
+                    let group = Self::buffer(&event);
+                    if let Err(e) = Self::handle_action(&tx, &xmtp, &outbox, event).await {
+                        Self::send_error(&tx, group, e);
                     }
                 }
             };
         }
     }
 
-    async fn welcome_message(tx: &Sender<Action>, xmtp: &AsyncXmtp) -> Result<()> {
+    /// Answer the actions sent before the wallet is registered with XMTP. Groups and messages need
+    /// an inbox, so only `/register`, `/generate` and `/me` are run until then. Returns how the
+    /// session ended, if it did before the wallet was registered.
+    async fn unregistered(
+        tx: &ActionSender,
+        xmtp: &AsyncXmtp,
+        events: &mut ReceiverStream<XMTPAction>,
+    ) -> Result<Option<Session>> {
+        let address = xmtp.identity().address;
+        let msg = format!("Created wallet {}, use `/register` to register it with XMTP", address);
+        tx.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)))?;
+        while let Some(event) = events.next().await {
+            match event {
+                XMTPAction::Register => match Self::register(tx, xmtp).await {
+                    Ok(()) => return Ok(None),
+                    Err(e) => Self::send_error(tx, vec![0], e),
+                },
+                XMTPAction::Generate => {
+                    let msg = "Switching to a new wallet".to_string();
+                    tx.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)))?;
+                    return Ok(Some(Session::Generate));
+                }
+                XMTPAction::Info => {
+                    if let Err(e) = Self::welcome_message(tx, xmtp).await {
+                        Self::send_error(tx, vec![0], e);
+                    }
+                }
+                event => Self::send_error(
+                    tx,
+                    Self::buffer(&event),
+                    anyhow!("{} is not registered with XMTP yet, use `/register` first", address),
+                ),
+            }
+        }
+        Ok(Some(Session::Closed))
+    }
+
+    /// Buffer to show the errors of `action` in
+    fn buffer(action: &XMTPAction) -> GroupId {
+        match action {
+            XMTPAction::SendMessage(group, _)
+            | XMTPAction::Invite(group, _)
+            | XMTPAction::Members(group)
+            | XMTPAction::GroupInfo(group) => group.id.clone(),
+            _ => vec![0],
+        }
+    }
+
+    async fn handle_action(
+        tx: &ActionSender,
+        xmtp: &AsyncXmtp,
+        outbox: &Outbox,
+        action: XMTPAction,
+    ) -> Result<()> {
+        match action {
+            XMTPAction::SendMessage(group, m) => {
+                if group.is_fake() {
+                    bail!("Invalid Buffer, cannot send MLS messages to this buffer.");
+                }
+                tx.send(Action::Outbox(outbox.push(group, m)))?;
+            }
+            XMTPAction::CreateGroup { name, description } => {
+                log::debug!("Creating MLS group");
+                let group = xmtp.create_group(name, description).await?;
+                tx.send(Action::NewGroups(vec![group.clone()]))?;
+                Self::send_info(tx, xmtp, group.clone()).await;
+                Self::send_members(tx, xmtp, group).await;
+            }
+            XMTPAction::Invite(group, user) => {
+                let user = if !user.starts_with("0x") { format!("0x{}", user) } else { user };
+                xmtp.invite_user(group.clone(), user).await?;
+                Self::send_members(tx, xmtp, group).await;
+            }
+            XMTPAction::Info => Self::welcome_message(tx, xmtp).await?,
+            XMTPAction::Members(group) => Self::send_members(tx, xmtp, group).await,
+            XMTPAction::GroupInfo(group) => {
+                tx.send(Action::GroupInfo(xmtp.group_info(group).await?))?
+            }
+            XMTPAction::InboxState => {
+                let state = xmtp.inbox_state().await.map_err(|e| e.to_string());
+                tx.send(Action::InboxState(state))?
+            }
+            XMTPAction::Join(target) => {
+                let group = xmtp.find_invite(&target).await?;
+                xmtp.set_consent(group.clone(), true)?;
+                tx.send(Action::NewGroups(vec![group.clone()]))?;
+                tx.send(Action::ChangeRoomByName(hex::encode(&group.id)))?;
+                Self::send_info(tx, xmtp, group.clone()).await;
+                Self::send_members(tx, xmtp, group).await;
+            }
+            XMTPAction::Invites => Self::send_invites(tx, xmtp).await?,
+            XMTPAction::Decline(target) => {
+                let group = xmtp.find_invite(&target).await?;
+                xmtp.set_consent(group.clone(), false)?;
+                let msg = format!("Declined to join {}", hex::encode(&group.id));
+                tx.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)))?;
+            }
+            XMTPAction::Register => Self::register(tx, xmtp).await?,
+            // the event loop starts a new session instead
+            XMTPAction::Generate => (),
+        }
+        Ok(())
+    }
+
+    /// Send what is left in the outbox, giving up after [`FLUSH_TIMEOUT`]. The messages that were
+    /// not sent are left in the outbox.
+    async fn flush(xmtp: &AsyncXmtp, outbox: &Outbox) {
+        let mut failed = Vec::new();
+        let send_all = async {
+            while let Some((group, m)) = outbox.front() {
+                if let Err(e) = xmtp.send_message(group.clone(), m.clone()).await {
+                    log::error!("Could not send message while shutting down {}", e);
+                    failed.push((group, m));
+                }
+                outbox.pop();
+            }
+        };
+        if tokio::time::timeout(FLUSH_TIMEOUT, send_all).await.is_err() {
+            log::warn!("Timed out sending the outbox");
+        }
+        let mut queue = outbox.lock();
+        for message in failed.into_iter().rev() {
+            queue.push_front(message);
+        }
+    }
+
+    /// Show a group we are streamed, if we joined it. Groups we were only welcomed into wait for
+    /// `/join`.
+    async fn new_group(tx: &ActionSender, xmtp: &AsyncXmtp, group: Group) -> Result<()> {
+        if xmtp.is_joined(&group)? {
+            log::debug!("Following conversation for group {:?}", group.id);
+            tx.send(Action::NewGroups(vec![group.clone()]))?;
+            Self::send_info(tx, xmtp, group.clone()).await;
+            Self::send_members(tx, xmtp, group).await;
+            return Ok(());
+        }
+        let id = hex::encode(&group.id);
+        let msg = format!(
+            "You were invited to group {}. `/join {}` it or `/invites decline {}`",
+            id, id, id
+        );
+        tx.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)))?;
+        Ok(())
+    }
+
+    /// Register the installation of `xmtp` if it is not already, telling the user how it went
+    async fn register(tx: &ActionSender, xmtp: &AsyncXmtp) -> Result<()> {
+        let Identity { address, inbox_id } = xmtp.identity();
+        let send = |msg: String| tx.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)));
+        if xmtp.is_registered() {
+            send(format!("{} is already registered with inbox {}", address, inbox_id))?;
+            return Ok(());
+        }
+        send(format!("Registering {}, signing the request with the local wallet", address))?;
+        xmtp.register().await?;
+        send(format!("Registered {} with inbox {}", address, inbox_id))?;
+        Ok(())
+    }
+
+    async fn send_invites(tx: &ActionSender, xmtp: &AsyncXmtp) -> Result<()> {
+        let invites = xmtp.invites().await?;
+        let mut msg = match invites.len() {
+            0 => "No pending invitations".to_string(),
+            n => format!("{} pending invitations, `/join` or `/invites decline` them", n),
+        };
+        for invite in invites {
+            let name = if invite.name.is_empty() { "(no name)" } else { &invite.name };
+            msg.push_str(&format!("\n    {} {}", hex::encode(&invite.id), name));
+        }
+        tx.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)))?;
+        Ok(())
+    }
+
+    /// Show an error in the buffer of `group`
+    fn send_error(tx: &ActionSender, group: GroupId, error: impl std::fmt::Display) {
+        log::debug!("XMTP action failed {}", error);
+        let _ = tx.send(Action::Error(AppError::new(ErrorSource::XMTP, group, error)));
+    }
+
+    /// Let the views know the current members of `group`
+    async fn send_members(tx: &ActionSender, xmtp: &AsyncXmtp, group: Group) {
+        let id = group.id.clone();
+        match xmtp.members(group).await {
+            Ok(members) => {
+                let _ = tx.send(Action::GroupMembers(id, members));
+            }
+            Err(e) => Self::send_error(tx, id, e),
+        }
+    }
+
+    /// Let the views know the name of `group` and the rest of its info
+    async fn send_info(tx: &ActionSender, xmtp: &AsyncXmtp, group: Group) {
+        let id = group.id.clone();
+        match xmtp.group_info(group).await {
+            Ok(info) => {
+                let _ = tx.send(Action::GroupInfo(info));
+            }
+            Err(e) => Self::send_error(tx, id, e),
+        }
+    }
+
+    async fn welcome_message(tx: &ActionSender, xmtp: &AsyncXmtp) -> Result<()> {
         let mut info_message = format!("-------------- Information --------------");
         info_message
             .push_str(&format!("\nWallet Address: 0x{}", hex::encode(xmtp.wallet.address())));
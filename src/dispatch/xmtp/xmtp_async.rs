@@ -10,8 +10,8 @@ use xmtp_api_grpc::grpc_api_helper::Client as ApiClient;
 use xmtp_id::associations::{generate_inbox_id, unverified::UnverifiedSignature};
 use xmtp_mls::{
     identity::IdentityStrategy,
-    groups::MlsGroup,
-    storage::{group_message::StoredGroupMessage, EncryptedMessageStore, StorageOption},
+    groups::{members::PermissionLevel, MlsGroup, PreconfiguredPolicies},
+    storage::{consent_record::ConsentState, group::GroupQueryArgs, group_message::StoredGroupMessage, EncryptedMessageStore, StorageOption},
     InboxOwner,
     subscriptions::SubscribeError,
     types::InstallationId
@@ -19,7 +19,7 @@ use xmtp_mls::{
 use xmtp_proto::xmtp::message_contents::{EncodedContent, ContentTypeId};
 use xmtp_mls::groups::GroupMetadataOptions;
 use prost::Message;
-use crate::{cli::XChatApp, types::Group};
+use crate::{cli::XChatApp, types::{Group, GroupInfo, Identity, InboxState, Member}};
 
 pub type Client = xmtp_mls::client::Client<ApiClient>;
 type ClientBuilder = xmtp_mls::builder::ClientBuilder<ApiClient>;
@@ -68,8 +68,10 @@ impl fmt::Debug for AsyncXmtp {
 }
 
 impl AsyncXmtp {
-    /// Generate a xmtp client from a random seed
-    pub async fn new_ephemeral(opts: XChatApp) -> Result<Self> {
+    /// Generate a xmtp client from a random seed. The client still needs to be [`registered`].
+    ///
+    /// [`registered`]: AsyncXmtp::register
+    pub async fn new_ephemeral(opts: &XChatApp) -> Result<Self> {
         let wallet = LocalWallet::new(&mut StdRng::from_entropy());
         let db_name = format!("{}-db.sqlite", hex::encode(wallet.address()));
         let mut db = crate::util::project_directory()
@@ -83,25 +85,33 @@ impl AsyncXmtp {
         let strategy = IdentityStrategy::new(inbox_id, wallet.get_address(), nonce, None);
 
         let client = Self::create_client(
-            &opts,
+            opts,
             db.clone(),
             strategy,
         )
         .await?;
+        Ok(Self { wallet, db, client: Arc::new(client) })
+    }
 
-        let identity = client.identity();
-        let mut signature_request = identity.signature_request().expect("cant be none");
-        let sig_bytes = wallet.sign(signature_request.signature_text().as_str())
-                .unwrap()
-                .into();
+    /// Whether the installation of this client was already added to its inbox
+    pub fn is_registered(&self) -> bool {
+        let identity = self.client.identity();
+        identity.is_ready() || identity.signature_request().is_none()
+    }
+
+    /// Sign the request to add this installation to the inbox of the wallet, and send it to XMTP
+    pub async fn register(&self) -> Result<()> {
+        let mut signature_request = self.client.identity().signature_request()
+            .ok_or(anyhow!("{} is already registered", self.identity().address))?;
+        let sig_bytes = self.wallet.sign(signature_request.signature_text().as_str())
+            .context("The wallet could not sign the registration")?
+            .into();
         let signature = UnverifiedSignature::new_recoverable_ecdsa(sig_bytes);
         signature_request
-            .add_signature(signature, client.scw_verifier())
-            .await
-            .unwrap();
-            let res = client.register_identity(signature_request).await?;
-        log::debug!("--------------------------- res: {:?}", res);
-        Ok(Self { wallet, db, client: Arc::new(client) })
+            .add_signature(signature, self.client.scw_verifier())
+            .await?;
+        self.client.register_identity(signature_request).await?;
+        Ok(())
     }
 
     async fn create_client(
@@ -164,9 +174,10 @@ impl AsyncXmtp {
         Ok(())
     }
 
-    pub async fn create_group(&self) -> Result<Group> {
+    pub async fn create_group(&self, name: Option<String>, description: Option<String>) -> Result<Group> {
         let client = self.client.clone();
-        let group = client.create_group(None, GroupMetadataOptions::default())?;
+        let options = GroupMetadataOptions { name, description, ..Default::default() };
+        let group = client.create_group(None, options)?;
         Ok(group.into())
     }
 
@@ -176,9 +187,110 @@ impl AsyncXmtp {
         Ok(())
     }
 
+    pub async fn members(&self, group: Group) -> Result<Vec<Member>> {
+        let group = group.into_mls(&self.client);
+        let members = group.members().await?.into_iter().map(|member| Member {
+            inbox_id: member.inbox_id,
+            addresses: member.account_addresses,
+            is_admin: member.permission_level == PermissionLevel::Admin,
+            is_super_admin: member.permission_level == PermissionLevel::SuperAdmin,
+        });
+        Ok(members.collect())
+    }
+
+    pub async fn group_info(&self, group: Group) -> Result<GroupInfo> {
+        self.info(&group.into_mls(&self.client))
+    }
+
+    fn info(&self, group: &MlsGroup<Client>) -> Result<GroupInfo> {
+        let provider = self.client.mls_provider()?;
+        let permissions = match group.permissions()?.preconfigured_policy() {
+            Ok(PreconfiguredPolicies::AllMembers) => "all members",
+            Ok(PreconfiguredPolicies::AdminsOnly) => "admins only",
+            Err(_) => "custom",
+        };
+        Ok(GroupInfo {
+            name: group.group_name(&provider)?,
+            description: group.group_description(&provider)?,
+            permissions: permissions.into(),
+            created_at: group.created_at_ns,
+            id: group.group_id.clone(),
+        })
+    }
+
+    /// Every group we are in, after fetching the welcomes sent to this installation
+    async fn sync_groups(&self) -> Result<Vec<MlsGroup<Client>>> {
+        let conn = self.client.store().conn()?;
+        self.client.sync_welcomes(&conn).await?;
+        Ok(self.client.find_groups(GroupQueryArgs::default())?)
+    }
+
+    /// Whether we joined the group, rather than only being welcomed into it
+    pub fn is_joined(&self, group: &Group) -> Result<bool> {
+        let group = group.clone().into_mls(&self.client);
+        Ok(group.consent_state()? == ConsentState::Allowed)
+    }
+
+    /// Groups we were welcomed into, but did not join or decline yet
+    pub async fn invites(&self) -> Result<Vec<GroupInfo>> {
+        let mut invites = Vec::new();
+        for group in self.sync_groups().await? {
+            if group.consent_state()? == ConsentState::Unknown {
+                invites.push(self.info(&group)?);
+            }
+        }
+        Ok(invites)
+    }
+
+    /// The group we were welcomed into and did not join or decline yet, whose name is `target`,
+    /// or whose hex encoded id starts with it
+    pub async fn find_invite(&self, target: &str) -> Result<Group> {
+        let (mut invites, mut answered) = (Vec::new(), Vec::new());
+        for group in self.sync_groups().await? {
+            let info = self.info(&group)?;
+            if !info.name.eq_ignore_ascii_case(target) && !hex::encode(&info.id).starts_with(&target.to_lowercase()) {
+                continue;
+            }
+            match group.consent_state()? {
+                ConsentState::Unknown => invites.push(group),
+                state => answered.push((hex::encode(&info.id), state)),
+            }
+        }
+        match (invites.len(), answered.first()) {
+            (0, Some((id, ConsentState::Allowed))) => Err(anyhow!("Already joined group {}", id)),
+            (0, Some((id, _))) => Err(anyhow!("Already declined group {}", id)),
+            (0, None) => Err(anyhow!("No group {} was found, see `/invites`", target)),
+            (1, _) => Ok(invites.remove(0).into()),
+            (n, _) => Err(anyhow!("{} groups match {}, use more of the group id", n, target)),
+        }
+    }
+
+    /// Join (`allowed`) or decline the group
+    pub fn set_consent(&self, group: Group, allowed: bool) -> Result<()> {
+        let state = if allowed { ConsentState::Allowed } else { ConsentState::Denied };
+        group.into_mls(&self.client).update_consent_state(state)?;
+        Ok(())
+    }
+
+    pub async fn inbox_state(&self) -> Result<InboxState> {
+        let state = self.client.inbox_state(true).await?;
+        Ok(InboxState {
+            inbox_id: state.inbox_id().to_string(),
+            addresses: state.account_addresses(),
+            installations: state.installation_ids(),
+        })
+    }
+
     pub async fn installation_public_key(&self) -> InstallationId {
         self.client.installation_public_key()
     }
+
+    pub fn identity(&self) -> Identity {
+        Identity {
+            address: format!("0x{}", hex::encode(self.wallet.address())),
+            inbox_id: self.client.inbox_id().to_string(),
+        }
+    }
 }
 
 impl Drop for AsyncXmtp {
@@ -0,0 +1,127 @@
+//! Panic and error hooks.
+//! Restore the terminal before reporting, so a crash does not leave the shell in raw mode on the
+//! alternate screen, and write a crash report with the last actions dispatched. Message contents,
+//! typed text and identities are left out of the report.
+
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fmt::{self, Write as _},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use crossterm::event::KeyCode;
+use lazy_static::lazy_static;
+
+use crate::{dispatch::Action, tui, util::get_data_dir};
+
+/// Amount of actions kept for crash reports
+const RECENT_ACTIONS: usize = 50;
+/// Longest an action is written to the report
+const MAX_ACTION_LEN: usize = 500;
+
+lazy_static! {
+    static ref ACTIONS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+}
+
+/// Text that stops formatting once it is [`MAX_ACTION_LEN`] characters long, so large actions
+/// are not formatted in full only to be cut
+#[derive(Default)]
+struct Truncated {
+    text: String,
+    len: usize,
+}
+
+impl fmt::Write for Truncated {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if self.len >= MAX_ACTION_LEN {
+                if self.len == MAX_ACTION_LEN {
+                    self.text.push('…');
+                    self.len += 1;
+                }
+                return Err(fmt::Error);
+            }
+            self.text.push(c);
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
+
+/// What to write instead of the actions carrying message contents, typed text, addresses or
+/// identities, as crash reports get attached to bug reports
+fn redacted(action: &Action) -> Option<&'static str> {
+    let redacted = match action {
+        Action::KeyPress(key) if matches!(key.code, KeyCode::Char(_)) => "KeyPress(Char(..))",
+        Action::Paste(_) => "Paste(..)",
+        Action::Composed(_) => "Composed(..)",
+        Action::FakeMessage(..) => "FakeMessage(..)",
+        Action::ReceiveMessages(_) => "ReceiveMessages(..)",
+        Action::ReceiveMessage(_) => "ReceiveMessage(..)",
+        Action::SendToBuffer(..) => "SendToBuffer(..)",
+        Action::Identity(_) => "Identity(..)",
+        Action::GroupMembers(..) => "GroupMembers(..)",
+        Action::InboxState(_) => "InboxState(..)",
+        Action::XMTP(_) => "XMTP(..)",
+        Action::Command(_) => "Command(..)",
+        _ => return None,
+    };
+    Some(redacted)
+}
+
+/// Remember `action` for the crash report
+pub fn record_action(action: &Action) {
+    let action = match redacted(action) {
+        Some(redacted) => redacted.to_string(),
+        None => {
+            let mut truncated = Truncated::default();
+            // fails once the action is too long, keeping what was written until then
+            let _ = write!(truncated, "{:?}", action);
+            truncated.text
+        }
+    };
+    if let Ok(mut actions) = ACTIONS.lock() {
+        if actions.len() == RECENT_ACTIONS {
+            actions.pop_front();
+        }
+        actions.push_back(action);
+    }
+}
+
+/// Errors returned from `main` restore the terminal themselves, only panics need a hook
+pub fn init() {
+    let (panic_hook, _) = color_eyre::config::HookBuilder::default().into_hooks();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = tui::restore();
+        eprintln!("{}", panic_hook.panic_report(info));
+        match write_report(&info.to_string()) {
+            Ok(path) => eprintln!("A crash report was written to {}", path.display()),
+            Err(e) => eprintln!("Could not write a crash report: {}", e),
+        }
+        // a panic in any task leaves the app unusable without the terminal
+        std::process::exit(1);
+    }));
+}
+
+fn write_report(panic: &str) -> Result<PathBuf> {
+    let mut report =
+        format!("{} {} crashed\n\n{}\n", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"), panic);
+    report.push_str(&format!("\nBacktrace:\n{}\n", Backtrace::force_capture()));
+    report.push_str("\nRecent actions, oldest first:\n");
+    if let Ok(actions) = ACTIONS.lock() {
+        for action in actions.iter() {
+            report.push_str(&format!("    {}\n", action));
+        }
+    }
+
+    let directory = get_data_dir();
+    std::fs::create_dir_all(&directory)?;
+    let time = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let path = directory.join(format!("{}-crash-{}.txt", env!("CARGO_PKG_NAME"), time));
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
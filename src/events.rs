@@ -1,18 +1,18 @@
 //! The XCHat Terminal Event Handler
 //! This event handler streams events from the terminal and converts them into an [`Action`]
 
-use crossterm::event::{Event, EventStream};
-use tokio::{sync::broadcast::Sender, task::JoinHandle};
+use crossterm::event::{Event, EventStream, KeyCode, KeyModifiers};
+use tokio::task::JoinHandle;
 use tokio_stream::StreamExt;
 
-use crate::dispatch::Action;
+use crate::dispatch::{mark_dirty, Action, ActionSender};
 
 pub struct Events {
-    tx: Sender<Action>,
+    tx: ActionSender,
 }
 
 impl Events {
-    pub fn new(tx: Sender<Action>) -> Self {
+    pub fn new(tx: ActionSender) -> Self {
         Self { tx }
     }
 
@@ -26,9 +26,19 @@ impl Events {
                 }
 
                 let res = match event.expect("Checked Error") {
+                    // raw mode keeps the terminal from stopping us on Ctrl-Z
+                    Event::Key(key_event)
+                        if key_event.code == KeyCode::Char('z')
+                            && key_event.modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        self.tx.send(Action::Suspend)
+                    }
                     Event::Key(key_event) => self.tx.send(Action::KeyPress(key_event)),
                     Event::Resize(x, y) => self.tx.send(Action::Resize(x, y)).map_err(Into::into),
-                    _ => continue,
+                    Event::Mouse(mouse_event) => self.tx.send(Action::Mouse(mouse_event)),
+                    Event::Paste(text) => self.tx.send(Action::Paste(text)),
+                    Event::FocusGained => self.tx.send(Action::FocusGained),
+                    Event::FocusLost => self.tx.send(Action::FocusLost),
                 };
 
                 if let Err(e) = res {
@@ -37,4 +47,25 @@ impl Events {
             }
         })
     }
+
+    /// Only quit on `q` or Ctrl-c while a recording is replayed, and redraw on resizes
+    pub fn spawn_replay_controls(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut events = EventStream::new();
+            while let Some(event) = events.next().await {
+                match event {
+                    Ok(Event::Key(key))
+                        if key.code == KeyCode::Char('q')
+                            || (key.code == KeyCode::Char('c')
+                                && key.modifiers.contains(KeyModifiers::CONTROL)) =>
+                    {
+                        let _ = self.tx.send(Action::Quit);
+                    }
+                    Ok(Event::Resize(..)) => mark_dirty(),
+                    Ok(_) => (),
+                    Err(e) => log::error!("Event Error, e={}", e),
+                }
+            }
+        })
+    }
 }
@@ -1,104 +1,159 @@
 mod cli;
 mod dispatch;
+mod errors;
 mod events;
 mod pages;
+mod tui;
 mod types;
 mod util;
 mod views;
 
-use std::io::stderr;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
-use crossterm::{
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-    ExecutableCommand,
-};
-use ratatui::prelude::*;
-use tokio::sync::{
-    broadcast::{self, Receiver},
-    mpsc,
+use tokio::{
+    sync::mpsc,
+    task::JoinHandle,
+    time::{timeout_at, Instant, MissedTickBehavior},
 };
 
 use crate::{
-    dispatch::{Action, Commands, Dispatcher, PageRender, Store, XMTP},
-    events::Events,
-    pages::ChatPage,
+    dispatch::{
+        recording::{self, Recorder, Replay},
+        Action, ActionReceiver, Commands, Dispatcher, Outbox, PageRender, XMTP,
+    },
+    pages::Router,
+    tui::Tui,
 };
 
-type CrosstermTerminal = Terminal<CrosstermBackend<std::io::Stderr>>;
-
 #[tokio::main]
 async fn main() -> Result<()> {
     // console_subscriber::init();
-    self::util::init_logging().map_err(|_| anyhow!("Logging did not init"))?;
+    self::errors::init();
+    let result = run().await;
+    // the error is printed once main returns, to the shell rather than the alternate screen
+    if result.is_err() {
+        let _ = tui::restore();
+    }
+    result
+}
+
+async fn run() -> Result<()> {
     #[allow(unused)]
     let app: cli::XChatApp = argh::from_env();
 
-    let (actions, actions_subscription) = broadcast::channel::<Action>(100);
+    // the subscription outlives the render loop, so the tasks can still send while shutting down
+    let (actions, mut actions_subscription) = dispatch::channel();
+    self::util::init_logging(actions.clone()).map_err(|_| anyhow!("Logging did not init"))?;
+
+    let recorder = app.record.as_deref().map(Recorder::create).transpose()?;
+    let replay = app.replay.as_deref().map(Replay::load).transpose()?;
 
+    let (mouse, replaying) = (app.mouse, replay.is_some());
     let (xmtp_tx, xmtp_rx) = mpsc::channel(100);
     let (command_tx, command_rx) = mpsc::channel(100);
+    // what the terminal, XMTP and the commands send is recorded, the views derive the rest
+    let input = match recorder {
+        Some(recorder) => recorder.tee(actions.clone()),
+        None => actions.clone(),
+    };
 
     // events
-    let xmtp = XMTP::new(actions.clone(), xmtp_rx, app).spawn();
-    let events = Events::new(actions.clone()).spawn();
-    let commands = Commands::new(actions.clone(), xmtp_tx.clone(), command_rx).spawn();
+    let outbox = Outbox::default();
+    let (commands, xmtp) = match replay {
+        Some(replay) => {
+            replay.spawn(actions.clone());
+            recording::fake_backend(actions.clone(), xmtp_rx, command_rx)
+        }
+        None => {
+            let xmtp = XMTP::new(input.clone(), xmtp_rx, app, outbox.clone()).spawn();
+            let commands = Commands::new(input.clone(), xmtp_tx.clone(), command_rx).spawn();
+            (commands, xmtp)
+        }
+    };
 
-    enable_raw_mode()?;
-    stderr().execute(EnterAlternateScreen)?;
-    let mut terminal = Terminal::new(CrosstermBackend::new(stderr()))?;
+    let mut tui = Tui::new(input, mouse, replaying)?;
+    tui.enter()?;
 
     // views
-    let chat_page = ChatPage::new(xmtp_tx, command_tx, actions.clone());
+    let router = Router::new(xmtp_tx, command_tx, actions.clone(), tui.captures_mouse());
 
-    if let Err(e) =
-        render_loop(&mut terminal, actions_subscription, chat_page, 1_000.0, 120.0).await
-    {
+    let result = render_loop(&mut tui, &mut actions_subscription, router, 1.0, 60.0).await;
+    // stop taking input before anything else shuts down, the outbox is flushed even if the
+    // terminal could not be restored
+    let exited = tui.exit();
+    if let Err(e) = result {
         log::error!("Error in render loop: {}", e);
         log::error!("Shutting down...")
     }
+    shutdown(commands, xmtp, outbox).await;
 
-    events.abort();
-    xmtp.abort();
-    commands.abort();
-    disable_raw_mode()?;
-    stderr().execute(LeaveAlternateScreen)?;
+    exited
+}
+
+/// How long to wait for the tasks to finish their work, all together
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
 
-    Ok(())
+/// Wait for the tasks to finish once the chat page dropped the senders to them. The commands task
+/// exits first, letting go of its sender to XMTP, which then flushes its outbox. Whatever is left
+/// in the outbox is printed, even if the tasks did not finish in time.
+async fn shutdown(commands: JoinHandle<()>, xmtp: JoinHandle<()>, outbox: Outbox) {
+    let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+    if timeout_at(deadline, commands).await.is_err() {
+        log::warn!("Commands did not shut down in time");
+    }
+    match timeout_at(deadline, xmtp).await {
+        Ok(Ok(())) => (),
+        Ok(Err(e)) => log::error!("XMTP task failed {}", e),
+        Err(_) => log::warn!("XMTP did not shut down in time"),
+    }
+    let unsent = outbox.take();
+    if !unsent.is_empty() {
+        eprintln!("{} messages could not be sent:", unsent.len());
+        for (group, message) in unsent {
+            eprintln!("    {}: {}", group.name(), message);
+        }
+    }
 }
 
 pub async fn render_loop(
-    terminal: &mut CrosstermTerminal,
-    mut events: Receiver<Action>,
-    mut chat_page: ChatPage<'_>,
+    tui: &mut Tui,
+    events: &mut ActionReceiver,
+    router: Router<'_>,
     tick_rate: f64,
-    frame_rate: f64,
+    max_frame_rate: f64,
 ) -> Result<()> {
     let tick_delay = std::time::Duration::from_secs_f64(1.0 / tick_rate);
-    let render_delay = std::time::Duration::from_secs_f64(1.0 / frame_rate);
+    let render_delay = std::time::Duration::from_secs_f64(1.0 / max_frame_rate);
     let mut tick_interval = tokio::time::interval(tick_delay);
     let mut render_interval = tokio::time::interval(render_delay);
+    // after being idle, draw the next change right away instead of catching up on frames
+    render_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
+    let mut dispatcher = Dispatcher::new(router, events);
     loop {
-        let stores = chat_page.stores();
-        let mut dispatcher = Dispatcher::new(stores, &mut events);
-        let dispatch = dispatcher.dispatch();
-
         let tick_delay = tick_interval.tick();
         let render_delay = render_interval.tick();
         tokio::select! {
-            action = dispatch => {
-                match action {
+            action = dispatcher.next() => {
+                // the terminal is handed over before stores see the action
+                match &action {
                     Action::Quit => break Ok(()),
-                    _ => continue,
+                    Action::Suspend => tui.suspend()?,
+                    Action::RunEditor(path) => tui.compose(path)?,
+                    Action::Resume => tui.resume()?,
+                    Action::ToggleMouse => tui.toggle_mouse()?,
+                    _ => (),
                 }
+                dispatcher.dispatch(action).await;
             },
             _ = tick_delay => {
-                continue
-                // What do here?
+                dispatcher.dispatch(Action::Tick).await;
             },
-            _ = render_delay => {
-                terminal.draw(|f| chat_page.render(f))?;
+            // frames are only drawn when a store changed something
+            _ = render_delay, if dispatch::is_dirty() => {
+                dispatch::clear_dirty();
+                tui.draw(|f| dispatcher.root().render(f))?;
             }
         }
     }
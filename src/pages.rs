@@ -1,4 +1,45 @@
 //! A Page is a composition of Views.
 mod chat;
+mod group_info;
+mod identity;
+mod router;
+mod settings;
 
-pub use chat::ChatPage;
+use ratatui::{prelude::*, widgets::*, Frame};
+
+pub use self::{
+    chat::ChatPage, group_info::GroupInfoPage, identity::IdentityPage, router::Router,
+    settings::SettingsPage,
+};
+use crate::types::Page;
+
+/// Draw the border of a page over the whole screen, with the keys moving between pages. Returns
+/// the area inside of it.
+fn page_area(frame: &mut Frame, page: Page) -> Rect {
+    let keys = Line::from(" g: group info  i: identity  s: settings  Esc: back ").right_aligned();
+    let block = Block::new().borders(Borders::ALL).title(format!(" {} ", page)).title_bottom(keys);
+    let area = block.inner(frame.area());
+    frame.render_widget(Clear, frame.area());
+    frame.render_widget(block, frame.area());
+    area
+}
+
+/// Draw a new frame for a change to a page, if the page is `shown`
+fn mark_shown(shown: bool) {
+    if shown {
+        crate::dispatch::mark_dirty();
+    }
+}
+
+/// Keys scrolling the lines of a page
+fn scroll(scroll: &mut u16, key: crossterm::event::KeyEvent) {
+    use crossterm::event::KeyCode;
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => *scroll = scroll.saturating_add(1),
+        KeyCode::PageUp => *scroll = scroll.saturating_sub(10),
+        KeyCode::PageDown => *scroll = scroll.saturating_add(10),
+        KeyCode::Home => *scroll = 0,
+        _ => (),
+    }
+}
@@ -4,11 +4,15 @@ use std::{future::Future, pin::Pin};
 
 use anyhow::Result;
 use ratatui::{prelude::*, Frame};
-use tokio::sync::{broadcast::Sender as BroadcastSender, mpsc::Sender};
+use tokio::sync::mpsc::Sender;
 
 use crate::{
-    dispatch::{Action, CommandAction, PageRender, Store, ViewRender, XMTPAction},
-    views::{ChatArea, ChatRooms, InputBox},
+    dispatch::{
+        mark_dirty, Action, ActionSender, CommandAction, PageRender, Store, ViewRender, XMTPAction,
+    },
+    views::{
+        ChatArea, ChatRooms, InputBox, Nicklist, RoomSwitcher, StatusBar, Toast, NICKLIST_WIDTH,
+    },
 };
 
 const MIN_CHAT_HEIGHT: usize = 1;
@@ -17,6 +21,10 @@ pub struct ChatPage<'a> {
     input_box: InputBox<'a>,
     chat_area: ChatArea,
     rooms: ChatRooms,
+    switcher: RoomSwitcher,
+    status_bar: StatusBar,
+    nicklist: Nicklist,
+    toast: Toast,
 }
 
 impl ChatPage<'_> {
@@ -24,15 +32,25 @@ impl ChatPage<'_> {
     pub fn new(
         xmtp: Sender<XMTPAction>,
         command: Sender<CommandAction>,
-        events: BroadcastSender<Action>,
+        events: ActionSender,
     ) -> Self {
-        let (input_box, chat_area, rooms) = (
-            InputBox::new(xmtp.clone(), command.clone()),
-            ChatArea::default(),
-            ChatRooms::new(events),
+        let (input_box, chat_area, rooms, switcher, nicklist) = (
+            InputBox::new(xmtp.clone(), command.clone(), events.clone()),
+            ChatArea::new(events.clone()),
+            ChatRooms::new(events.clone(), xmtp.clone()),
+            RoomSwitcher::new(events.clone()),
+            Nicklist::new(xmtp, events),
         );
 
-        Self { input_box, chat_area, rooms }
+        Self {
+            input_box,
+            chat_area,
+            rooms,
+            switcher,
+            status_bar: StatusBar::default(),
+            nicklist,
+            toast: Toast::default(),
+        }
     }
 }
 
@@ -40,11 +58,29 @@ mod buffers {
     pub const CHAT_AREA: usize = 1;
 }
 
+impl ChatPage<'_> {
+    pub fn nicklist_visible(&self) -> bool {
+        self.nicklist.is_visible()
+    }
+
+    /// Whether a page is shown over the chat. The room switcher is not opened behind it.
+    pub fn set_covered(&mut self, covered: bool) {
+        self.switcher.set_covered(covered);
+    }
+}
+
 impl Store for ChatPage<'_> {
+    fn handles(&self, action: &Action) -> bool {
+        matches!(action, Action::Resize(..))
+    }
+
     fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
         let future = async move {
             match action {
-                Action::Resize(x, y) => log::debug!("Resizing Chat Page {x}:{y}"),
+                Action::Resize(x, y) => {
+                    log::debug!("Resizing Chat Page {x}:{y}");
+                    mark_dirty();
+                }
                 _ => (),
             }
             Ok(())
@@ -57,6 +93,10 @@ impl Store for ChatPage<'_> {
             &mut self.input_box as &mut dyn Store,
             &mut self.chat_area as &mut dyn Store,
             &mut self.rooms as &mut dyn Store,
+            &mut self.switcher as &mut dyn Store,
+            &mut self.status_bar as &mut dyn Store,
+            &mut self.nicklist as &mut dyn Store,
+            &mut self.toast as &mut dyn Store,
         ]
     }
 }
@@ -65,18 +105,40 @@ impl PageRender for ChatPage<'_> {
     fn render(&self, frame: &mut Frame) {
         let screen = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints(vec![Constraint::Percentage(10), Constraint::Percentage(90)])
+            .constraints(vec![
+                self.rooms.width().map(Constraint::Length).unwrap_or(Constraint::Percentage(10)),
+                Constraint::Fill(1),
+            ])
             .split(frame.area());
 
         let height = std::cmp::max(self.input_box.lines().len(), MIN_CHAT_HEIGHT) as u16 + 2;
 
         let chat_area = Layout::default()
             .direction(Direction::Vertical)
-            .constraints(vec![Constraint::Min(10), Constraint::Length(height)])
+            .constraints(vec![
+                Constraint::Min(10),
+                Constraint::Length(1),
+                Constraint::Length(height),
+            ])
             .split(screen[buffers::CHAT_AREA]);
 
+        let nicklist_width = if self.nicklist.is_visible() { NICKLIST_WIDTH } else { 0 };
+        let [messages, nicklist] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Length(nicklist_width)])
+                .areas(chat_area[0]);
+
         self.rooms.render(frame, screen[0].into());
-        self.chat_area.render(frame, chat_area[0].into());
-        self.input_box.render(frame, chat_area[1].into());
+        self.chat_area.render(frame, messages.into());
+        if self.nicklist.is_visible() {
+            self.nicklist.render(frame, nicklist.into());
+        }
+        self.status_bar.render(frame, chat_area[1].into());
+        self.input_box.render(frame, chat_area[2].into());
+        if self.toast.is_visible() {
+            self.toast.render(frame, messages.into());
+        }
+        if self.switcher.is_open() {
+            self.switcher.render(frame, frame.area().into());
+        }
     }
 }
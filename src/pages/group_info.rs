@@ -0,0 +1,167 @@
+//! Group Info Page
+//! Name, description, permissions and members of the focused group.
+use std::{collections::HashMap, future::Future, pin::Pin};
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{prelude::*, widgets::*, Frame};
+use tokio::sync::mpsc::Sender;
+
+use crate::{
+    dispatch::{Action, PageRender, Store, XMTPAction},
+    types::{ErrorSource, Group, GroupId, GroupInfo, Member, Page},
+};
+
+pub struct GroupInfoPage {
+    xmtp: Sender<XMTPAction>,
+    focused_group: Group,
+    info: HashMap<GroupId, GroupInfo>,
+    members: HashMap<GroupId, Vec<Member>>,
+    /// Why the info of the focused group could not be fetched
+    error: Option<String>,
+    scroll: u16,
+    /// Whether the page is on top of the router's stack
+    shown: bool,
+}
+
+impl GroupInfoPage {
+    pub fn new(xmtp: Sender<XMTPAction>) -> Self {
+        Self {
+            xmtp,
+            focused_group: Group::new_fake(0),
+            info: HashMap::new(),
+            members: HashMap::new(),
+            error: None,
+            scroll: 0,
+            shown: false,
+        }
+    }
+
+    pub fn set_shown(&mut self, shown: bool) {
+        self.shown = shown;
+    }
+
+    /// Fetch the info of the focused group again, as it may have changed since it was shown
+    pub async fn open(&mut self) -> Result<()> {
+        self.scroll = 0;
+        self.error = None;
+        if !self.focused_group.is_fake() {
+            self.xmtp.send(XMTPAction::GroupInfo(self.focused_group.clone())).await?;
+            self.xmtp.send(XMTPAction::Members(self.focused_group.clone())).await?;
+        }
+        Ok(())
+    }
+
+    pub fn handle_key_event(&mut self, key: KeyEvent) {
+        super::scroll(&mut self.scroll, key);
+    }
+
+    fn lines(&self) -> Vec<Line<'_>> {
+        let group = &self.focused_group;
+        if group.is_fake() {
+            return vec![Line::raw(format!("The {} buffer is not a group", group.name()))];
+        }
+        let mut lines = vec![Line::raw(format!("Id: {}", hex::encode(&group.id)))];
+        if let Some(error) = &self.error {
+            lines.push(Line::styled(error.as_str(), Style::new().fg(Color::LightRed)));
+        }
+        match self.info.get(&group.id) {
+            Some(info) => {
+                let or_none = |s: &str| if s.is_empty() { "(none)".to_string() } else { s.into() };
+                let created = chrono::DateTime::from_timestamp_nanos(info.created_at)
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M:%S");
+                lines.extend([
+                    Line::raw(format!("Name: {}", or_none(&info.name))),
+                    Line::raw(format!("Description: {}", or_none(&info.description))),
+                    Line::raw(format!("Permissions: {}", info.permissions)),
+                    Line::raw(format!("Created: {}", created)),
+                ]);
+            }
+            None if self.error.is_none() => lines.push(Line::raw("Loading…")),
+            None => (),
+        }
+        lines.push(Line::raw(""));
+
+        let members = self.members.get(&group.id).map(Vec::as_slice).unwrap_or(&[]);
+        lines.push(Line::styled(
+            format!("Members ({})", members.len()),
+            Style::new().add_modifier(Modifier::BOLD),
+        ));
+        for member in members {
+            let role = if member.is_super_admin {
+                "super admin"
+            } else if member.is_admin {
+                "admin"
+            } else {
+                "member"
+            };
+            lines.push(Line::raw(format!("  {} ({})", member.inbox_id, role)));
+            for address in &member.addresses {
+                lines.push(Line::raw(format!("    {}", address)));
+            }
+        }
+        lines
+    }
+}
+
+impl Store for GroupInfoPage {
+    fn handles(&self, action: &Action) -> bool {
+        matches!(
+            action,
+            Action::SetFocusedGroup(_)
+                | Action::GroupInfo(_)
+                | Action::GroupMembers(..)
+                | Action::Error(_)
+                | Action::ClearGroups
+        )
+    }
+
+    fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
+        let future = async move {
+            // only the focused group is shown
+            let shown = match action {
+                Action::SetFocusedGroup(group) => {
+                    self.focused_group = group;
+                    true
+                }
+                Action::GroupInfo(info) => {
+                    let shown = info.id == self.focused_group.id;
+                    self.info.insert(info.id.clone(), info);
+                    shown
+                }
+                Action::GroupMembers(group_id, members) => {
+                    let shown = group_id == self.focused_group.id;
+                    self.members.insert(group_id, members);
+                    shown
+                }
+                Action::Error(error)
+                    if error.source == ErrorSource::XMTP
+                        && error.group == self.focused_group.id =>
+                {
+                    self.error = Some(error.message);
+                    true
+                }
+                Action::ClearGroups => {
+                    self.info.clear();
+                    self.members.clear();
+                    self.error = None;
+                    true
+                }
+                _ => false,
+            };
+            super::mark_shown(self.shown && shown);
+            Ok(())
+        };
+        Box::pin(future)
+    }
+}
+
+impl PageRender for GroupInfoPage {
+    fn render(&self, frame: &mut Frame) {
+        let area = super::page_area(frame, Page::GroupInfo);
+        let paragraph =
+            Paragraph::new(self.lines()).wrap(Wrap { trim: false }).scroll((self.scroll, 0));
+        frame.render_widget(paragraph, area);
+    }
+}
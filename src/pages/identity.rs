@@ -0,0 +1,122 @@
+//! Identity Page
+//! Our inbox, with the wallet addresses and installations associated with it.
+use std::{future::Future, pin::Pin};
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{prelude::*, widgets::*, Frame};
+use tokio::sync::mpsc::Sender;
+
+use crate::{
+    dispatch::{Action, PageRender, Store, XMTPAction},
+    types::{Identity, InboxState, Page},
+};
+
+pub struct IdentityPage {
+    xmtp: Sender<XMTPAction>,
+    me: Option<Identity>,
+    state: Option<InboxState>,
+    /// Whether the inbox state was asked for and has not arrived yet
+    loading: bool,
+    /// Why the inbox state could not be fetched
+    error: Option<String>,
+    scroll: u16,
+    /// Whether the page is on top of the router's stack
+    shown: bool,
+}
+
+impl IdentityPage {
+    pub fn new(xmtp: Sender<XMTPAction>) -> Self {
+        Self { xmtp, me: None, state: None, loading: false, error: None, scroll: 0, shown: false }
+    }
+
+    pub fn set_shown(&mut self, shown: bool) {
+        self.shown = shown;
+    }
+
+    /// Fetch the inbox state again, installations may have been added since it was shown
+    pub async fn open(&mut self) -> Result<()> {
+        self.scroll = 0;
+        self.error = None;
+        if self.me.is_some() {
+            self.xmtp.send(XMTPAction::InboxState).await?;
+            self.loading = true;
+        }
+        Ok(())
+    }
+
+    pub fn handle_key_event(&mut self, key: KeyEvent) {
+        super::scroll(&mut self.scroll, key);
+    }
+
+    fn lines(&self) -> Vec<Line<'_>> {
+        let Some(me) = &self.me else {
+            return vec![Line::raw("Not connected to XMTP yet")];
+        };
+        let bold = Style::new().add_modifier(Modifier::BOLD);
+        let mut lines = vec![
+            Line::raw(format!("Inbox id: {}", me.inbox_id)),
+            Line::raw(format!("Wallet: {}", me.address)),
+        ];
+        if let Some(error) = &self.error {
+            lines.push(Line::styled(error.as_str(), Style::new().fg(Color::LightRed)));
+        }
+        let Some(state) = &self.state else {
+            if self.loading {
+                lines.push(Line::raw("Loading…"));
+            }
+            return lines;
+        };
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(format!("Addresses ({})", state.addresses.len()), bold));
+        lines.extend(state.addresses.iter().map(|address| Line::raw(format!("  {}", address))));
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(format!("Installations ({})", state.installations.len()), bold));
+        lines.extend(
+            state.installations.iter().map(|id| Line::raw(format!("  {}", hex::encode(id)))),
+        );
+        lines
+    }
+}
+
+impl Store for IdentityPage {
+    fn handles(&self, action: &Action) -> bool {
+        matches!(action, Action::Identity(_) | Action::InboxState(_) | Action::ClearGroups)
+    }
+
+    fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
+        let future = async move {
+            match action {
+                Action::Identity(identity) => self.me = Some(identity),
+                Action::InboxState(Ok(state)) => {
+                    self.state = Some(state);
+                    self.loading = false;
+                }
+                Action::InboxState(Err(error)) => {
+                    self.error = Some(error);
+                    self.loading = false;
+                }
+                // the inbox of the previous identity, the new one is sent once it is created
+                Action::ClearGroups => {
+                    self.me = None;
+                    self.state = None;
+                    self.error = None;
+                    self.loading = false;
+                }
+                _ => return Ok(()),
+            }
+            super::mark_shown(self.shown);
+            Ok(())
+        };
+        Box::pin(future)
+    }
+}
+
+impl PageRender for IdentityPage {
+    fn render(&self, frame: &mut Frame) {
+        let area = super::page_area(frame, Page::Identity);
+        let paragraph =
+            Paragraph::new(self.lines()).wrap(Wrap { trim: false }).scroll((self.scroll, 0));
+        frame.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,170 @@
+//! Page Router
+//! Shows the chat, or the last of the pages opened over it. Alt-g, Alt-i and Alt-s open the group
+//! info, identity and settings pages from the chat, Esc goes back to the page under the current
+//! one.
+use std::{future::Future, pin::Pin};
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::Frame;
+use tokio::sync::mpsc::Sender;
+
+use super::{ChatPage, GroupInfoPage, IdentityPage, SettingsPage};
+use crate::{
+    dispatch::{mark_dirty, Action, ActionSender, CommandAction, PageRender, Store, XMTPAction},
+    types::Page,
+};
+
+pub struct Router<'a> {
+    chat: ChatPage<'a>,
+    group_info: GroupInfoPage,
+    identity: IdentityPage,
+    settings: SettingsPage,
+    /// Pages opened over the chat, the last one is shown
+    stack: Vec<Page>,
+    events: ActionSender,
+    /// Whether a popup of the chat has taken over key presses
+    popup: bool,
+}
+
+impl Router<'_> {
+    /// `mouse` is whether the terminal captures the mouse
+    pub fn new(
+        xmtp: Sender<XMTPAction>,
+        command: Sender<CommandAction>,
+        events: ActionSender,
+        mouse: bool,
+    ) -> Self {
+        let chat = ChatPage::new(xmtp.clone(), command, events.clone());
+        let settings = SettingsPage::new(events.clone(), mouse, chat.nicklist_visible());
+        Self {
+            chat,
+            group_info: GroupInfoPage::new(xmtp.clone()),
+            identity: IdentityPage::new(xmtp),
+            settings,
+            stack: Vec::new(),
+            events,
+            popup: false,
+        }
+    }
+
+    fn page(key: KeyCode) -> Option<Page> {
+        match key {
+            KeyCode::Char('g') => Some(Page::GroupInfo),
+            KeyCode::Char('i') => Some(Page::Identity),
+            KeyCode::Char('s') => Some(Page::Settings),
+            _ => None,
+        }
+    }
+
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(top) = self.stack.last() else {
+            if !self.popup && key.modifiers.intersects(KeyModifiers::ALT) {
+                if let Some(page) = Self::page(key.code) {
+                    self.events.send(Action::OpenPage(page))?;
+                }
+            }
+            return Ok(());
+        };
+        // the keys of a page move its selection or scroll it
+        mark_dirty();
+        if key.code == KeyCode::Esc {
+            self.events.send(Action::ClosePage)?;
+            return Ok(());
+        }
+        if let Some(page) = Self::page(key.code) {
+            self.events.send(Action::OpenPage(page))?;
+            return Ok(());
+        }
+        match top {
+            Page::GroupInfo => self.group_info.handle_key_event(key),
+            Page::Identity => self.identity.handle_key_event(key),
+            Page::Settings => self.settings.handle_key_event(key)?,
+        }
+        Ok(())
+    }
+
+    async fn open(&mut self, page: Page) -> Result<()> {
+        if self.stack.last() == Some(&page) {
+            return Ok(());
+        }
+        // the views of the chat ignore keys while it is covered
+        if self.stack.is_empty() {
+            self.events.send(Action::SetPopup(true))?;
+        }
+        // a page opened again moves to the top, so Esc goes back through each page once
+        self.stack.retain(|opened| *opened != page);
+        self.stack.push(page);
+        self.set_shown();
+        mark_dirty();
+        match page {
+            Page::GroupInfo => self.group_info.open().await,
+            Page::Identity => self.identity.open().await,
+            Page::Settings => Ok(()),
+        }
+    }
+
+    /// Let the pages know which one is on top, the others do not draw a new frame as they change
+    fn set_shown(&mut self) {
+        let top = self.stack.last();
+        self.chat.set_covered(top.is_some());
+        self.group_info.set_shown(top == Some(&Page::GroupInfo));
+        self.identity.set_shown(top == Some(&Page::Identity));
+        self.settings.set_shown(top == Some(&Page::Settings));
+    }
+
+    fn close(&mut self) -> Result<()> {
+        if self.stack.pop().is_some() {
+            self.set_shown();
+            mark_dirty();
+            if self.stack.is_empty() {
+                self.events.send(Action::SetPopup(false))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Store for Router<'_> {
+    fn handles(&self, action: &Action) -> bool {
+        matches!(
+            action,
+            Action::KeyPress(_) | Action::SetPopup(_) | Action::OpenPage(_) | Action::ClosePage
+        )
+    }
+
+    fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
+        let future = async move {
+            match action {
+                Action::KeyPress(key) => self.handle_key_event(key).await?,
+                Action::SetPopup(open) => self.popup = open,
+                Action::OpenPage(page) => self.open(page).await?,
+                Action::ClosePage => self.close()?,
+                _ => (),
+            }
+            Ok(())
+        };
+        Box::pin(future)
+    }
+
+    // every page is kept up to date, to be shown as soon as it is opened
+    fn stores(&mut self) -> Vec<&mut dyn Store> {
+        vec![
+            &mut self.chat as &mut dyn Store,
+            &mut self.group_info as &mut dyn Store,
+            &mut self.identity as &mut dyn Store,
+            &mut self.settings as &mut dyn Store,
+        ]
+    }
+}
+
+impl PageRender for Router<'_> {
+    fn render(&self, frame: &mut Frame) {
+        match self.stack.last() {
+            None => self.chat.render(frame),
+            Some(Page::GroupInfo) => self.group_info.render(frame),
+            Some(Page::Identity) => self.identity.render(frame),
+            Some(Page::Settings) => self.settings.render(frame),
+        }
+    }
+}
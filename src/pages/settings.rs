@@ -0,0 +1,138 @@
+//! Settings Page
+//! Toggles for what can otherwise be changed with keys or commands while chatting.
+use std::{future::Future, pin::Pin};
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*, Frame};
+
+use crate::{
+    dispatch::{Action, ActionSender, PageRender, Store},
+    types::{ConnectionState, Network, Page},
+    util::{log_filter, set_log_filter},
+};
+
+/// Filters of the log buffer cycled through by the settings page
+const LOG_LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    Mouse,
+    Nicklist,
+    LogLevel,
+}
+
+const SETTINGS: [Setting; 3] = [Setting::Mouse, Setting::Nicklist, Setting::LogLevel];
+
+pub struct SettingsPage {
+    events: ActionSender,
+    /// index into SETTINGS
+    selected: usize,
+    mouse: bool,
+    nicklist: bool,
+    connection: Option<(Network, ConnectionState)>,
+    /// Whether the page is on top of the router's stack
+    shown: bool,
+}
+
+impl SettingsPage {
+    /// `mouse` and `nicklist` are whether the mouse is captured and the nicklist shown, the
+    /// toggle actions keep them up to date from then on
+    pub fn new(events: ActionSender, mouse: bool, nicklist: bool) -> Self {
+        Self { events, selected: 0, mouse, nicklist, connection: None, shown: false }
+    }
+
+    pub fn set_shown(&mut self, shown: bool) {
+        self.shown = shown;
+    }
+
+    pub fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = std::cmp::min(self.selected + 1, SETTINGS.len() - 1)
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => self.change(SETTINGS[self.selected])?,
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn change(&mut self, setting: Setting) -> Result<()> {
+        match setting {
+            Setting::Mouse => self.events.send(Action::ToggleMouse)?,
+            Setting::Nicklist => self.events.send(Action::ToggleNicklist)?,
+            Setting::LogLevel => {
+                let current = LOG_LEVELS.iter().position(|level| *level == log_filter());
+                // a filter set with `/debug` starts over from the least verbose level
+                let next = current.map(|i| (i + 1) % LOG_LEVELS.len()).unwrap_or(0);
+                set_log_filter(LOG_LEVELS[next])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn value(&self, setting: Setting) -> String {
+        let on_off = |on: bool| if on { "on".to_string() } else { "off".to_string() };
+        match setting {
+            Setting::Mouse => on_off(self.mouse),
+            Setting::Nicklist => on_off(self.nicklist),
+            Setting::LogLevel => log_filter(),
+        }
+    }
+
+    fn name(setting: Setting) -> &'static str {
+        match setting {
+            Setting::Mouse => "Capture mouse",
+            Setting::Nicklist => "Show nicklist",
+            Setting::LogLevel => "Log buffer level",
+        }
+    }
+}
+
+impl Store for SettingsPage {
+    fn handles(&self, action: &Action) -> bool {
+        matches!(action, Action::ToggleMouse | Action::ToggleNicklist | Action::Connection(..))
+    }
+
+    fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
+        let future = async move {
+            match action {
+                Action::ToggleMouse => self.mouse = !self.mouse,
+                Action::ToggleNicklist => self.nicklist = !self.nicklist,
+                Action::Connection(network, state) => self.connection = Some((network, state)),
+                _ => (),
+            }
+            // every action handled changes a value on the page
+            super::mark_shown(self.shown);
+            Ok(())
+        };
+        Box::pin(future)
+    }
+}
+
+impl PageRender for SettingsPage {
+    fn render(&self, frame: &mut Frame) {
+        let area = super::page_area(frame, Page::Settings);
+        let [settings, network] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+
+        let rows = SETTINGS.iter().enumerate().map(|(index, setting)| {
+            let row = Row::new([Self::name(*setting).to_string(), self.value(*setting)]);
+            if index == self.selected {
+                row.style(Style::new().fg(Color::LightGreen).add_modifier(Modifier::BOLD))
+            } else {
+                row
+            }
+        });
+        let table = Table::new(rows, [Constraint::Length(20), Constraint::Fill(1)]);
+        frame.render_widget(table, settings);
+
+        let connection = match self.connection {
+            Some((network, state)) => format!("Network: {} ({})", network, state),
+            None => "Network: offline".to_string(),
+        };
+        let help = "  ↑/↓: select  Enter: change";
+        frame.render_widget(Paragraph::new(format!("{}{}", connection, help)), network);
+    }
+}
@@ -0,0 +1,192 @@
+//! Terminal setup and teardown.
+//! Owns the terminal and the task streaming terminal [`Events`], so the application can give the
+//! terminal back to the shell (or another program) and take it over again.
+
+use std::{io::stderr, path::Path};
+
+use anyhow::Result;
+use crossterm::{
+    cursor::Show,
+    event::{
+        DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+        EnableFocusChange, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+    ExecutableCommand,
+};
+use ratatui::prelude::*;
+use tokio::task::JoinHandle;
+
+use crate::{
+    dispatch::{Action, ActionSender},
+    events::Events,
+};
+
+type CrosstermTerminal = Terminal<CrosstermBackend<std::io::Stderr>>;
+
+pub struct Tui {
+    terminal: CrosstermTerminal,
+    actions: ActionSender,
+    events: Option<JoinHandle<()>>,
+    /// Whether the terminal reports keys with the kitty keyboard protocol,
+    /// making modifiers like Shift-Enter detectable
+    enhanced_keyboard: bool,
+    /// Whether mouse events are captured. Terminal text selection does not work while they are.
+    mouse: bool,
+    /// Whether the terminal is in raw mode on the alternate screen, and may be drawn to
+    active: bool,
+    /// Whether a recording is replayed, in which case the terminal only takes keys to quit
+    replaying: bool,
+}
+
+impl Tui {
+    /// `mouse` is whether the mouse is captured from the start
+    pub fn new(actions: ActionSender, mouse: bool, replaying: bool) -> Result<Self> {
+        let terminal = Terminal::new(CrosstermBackend::new(stderr()))?;
+        Ok(Self {
+            terminal,
+            actions,
+            events: None,
+            enhanced_keyboard: false,
+            mouse,
+            active: false,
+            replaying,
+        })
+    }
+
+    /// Enter raw mode on the alternate screen and start streaming terminal events
+    pub fn enter(&mut self) -> Result<()> {
+        enable_raw_mode()?;
+        stderr()
+            .execute(EnterAlternateScreen)?
+            .execute(EnableBracketedPaste)?
+            .execute(EnableFocusChange)?;
+        // querying the terminal reads from stdin, so this must happen before events are streamed
+        self.enhanced_keyboard = supports_keyboard_enhancement().unwrap_or(false);
+        if self.enhanced_keyboard {
+            stderr().execute(PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES,
+            ))?;
+        }
+        if self.mouse {
+            stderr().execute(EnableMouseCapture)?;
+        }
+        let events = Events::new(self.actions.clone());
+        // live input would mix with the replayed one
+        self.events =
+            Some(if self.replaying { events.spawn_replay_controls() } else { events.spawn() });
+        self.active = true;
+        Ok(())
+    }
+
+    /// Stop streaming terminal events and restore the terminal
+    pub fn exit(&mut self) -> Result<()> {
+        if let Some(events) = self.events.take() {
+            events.abort();
+        }
+        if self.mouse {
+            stderr().execute(DisableMouseCapture)?;
+        }
+        if self.enhanced_keyboard {
+            stderr().execute(PopKeyboardEnhancementFlags)?;
+        }
+        stderr().execute(DisableFocusChange)?.execute(DisableBracketedPaste)?;
+        disable_raw_mode()?;
+        stderr().execute(LeaveAlternateScreen)?;
+        self.active = false;
+        Ok(())
+    }
+
+    /// Give the terminal back to the shell and stop the process, like Ctrl-Z outside of raw mode.
+    /// Once the shell continues us with SIGCONT, [`Action::Resume`] takes the terminal over again.
+    /// Tasks pick up where they left off, with incoming messages buffered in their streams.
+    pub fn suspend(&mut self) -> Result<()> {
+        self.exit()?;
+        #[cfg(unix)]
+        signal_hook::low_level::raise(signal_hook::consts::SIGTSTP)?;
+        self.actions.send(Action::Resume)?;
+        Ok(())
+    }
+
+    /// Take the terminal over again, redrawing everything on the next frame
+    pub fn resume(&mut self) -> Result<()> {
+        self.enter()?;
+        self.terminal.clear()?;
+        crate::dispatch::mark_dirty();
+        Ok(())
+    }
+
+    /// Hand the terminal over to `$EDITOR` on the draft at `path`, sending what was written as
+    /// [`Action::Composed`]. The editor is not run while replaying, the recording has what was
+    /// written then.
+    pub fn compose(&mut self, path: &Path) -> Result<()> {
+        if !self.replaying {
+            self.exit()?;
+            let text = Self::run_editor(path);
+            self.resume()?;
+            if let Some(text) = text {
+                self.actions.send(Action::Composed(text))?;
+            }
+        }
+        if let Err(e) = std::fs::remove_file(path) {
+            log::error!("Could not remove composed message {}", e);
+        }
+        Ok(())
+    }
+
+    /// Run the editor on `path`, returning the text it left there if it succeeded
+    fn run_editor(path: &Path) -> Option<String> {
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| "vi".into());
+        let mut args = editor.split_whitespace();
+        let program = args.next().unwrap_or("vi");
+        match std::process::Command::new(program).args(args).arg(path).status() {
+            Ok(status) if status.success() => match std::fs::read_to_string(path) {
+                Ok(text) => return Some(text),
+                Err(e) => log::error!("Could not read composed message {}", e),
+            },
+            Ok(status) => log::warn!("Editor `{}` exited with {}", editor, status),
+            Err(e) => log::error!("Could not run editor `{}` {}", editor, e),
+        }
+        None
+    }
+
+    pub fn captures_mouse(&self) -> bool {
+        self.mouse
+    }
+
+    /// Start or stop capturing mouse events
+    pub fn toggle_mouse(&mut self) -> Result<()> {
+        self.mouse = !self.mouse;
+        if self.mouse {
+            stderr().execute(EnableMouseCapture)?;
+        } else {
+            stderr().execute(DisableMouseCapture)?;
+        }
+        Ok(())
+    }
+
+    /// Draw a frame, unless the terminal was given back
+    pub fn draw(&mut self, render: impl FnOnce(&mut Frame)) -> Result<()> {
+        if self.active {
+            self.terminal.draw(render)?;
+        }
+        Ok(())
+    }
+}
+
+/// Restore the terminal without a [`Tui`], whatever state it was left in. Used when crashing.
+pub fn restore() -> Result<()> {
+    let _ = stderr().execute(DisableMouseCapture);
+    let _ = stderr().execute(PopKeyboardEnhancementFlags);
+    let _ = stderr().execute(DisableFocusChange);
+    let _ = stderr().execute(DisableBracketedPaste);
+    disable_raw_mode()?;
+    stderr().execute(LeaveAlternateScreen)?.execute(Show)?;
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use std::fmt;
 
+use serde::{Deserialize, Serialize};
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct GroupIdWrapper(pub Vec<u8>);
 
@@ -19,8 +21,11 @@ impl From<Vec<u8>> for GroupIdWrapper {
 }
 pub type GroupId = Vec<u8>;
 
+/// Id of the fake buffer tracing records are shown in
+pub const LOG_BUFFER: u8 = 1;
+
 // can form a group by calling `MlsGroup::new()` and passing our client reference
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct Group {
     pub id: GroupId,
     // timestamp this group was created at. Used for reconstructing MlsGroup.
@@ -42,4 +47,176 @@ impl Group {
     pub fn is_fake(&self) -> bool {
         self.is_fake
     }
+
+    /// Name of the buffer showing this group
+    pub fn name(&self) -> String {
+        match (self.is_fake, self.id.as_slice()) {
+            (true, [LOG_BUFFER]) => "log".into(),
+            (true, _) => "xchat".into(),
+            (false, _) => GroupIdWrapper::from(self.id.clone()).to_string(),
+        }
+    }
+}
+
+/// The identity of this session
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct Identity {
+    /// Wallet address, hex encoded with a `0x` prefix
+    pub address: String,
+    pub inbox_id: String,
+}
+
+/// Activity in a buffer that is not focused, from least to most important
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Activity {
+    Message,
+    /// A message mentioning us
+    Mention,
+    /// A message in a conversation between us and one other user
+    Private,
+}
+
+/// A buffer with activity, as shown in the hotlist
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct HotlistEntry {
+    /// Number of the buffer, starting at 1
+    pub number: usize,
+    pub name: String,
+    pub activity: Activity,
+}
+
+/// XMTP network the client connects to
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum Network {
+    Local,
+    Dev,
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Network::Local => write!(f, "local"),
+            Network::Dev => write!(f, "dev"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+impl fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionState::Connecting => write!(f, "connecting"),
+            ConnectionState::Connected => write!(f, "connected"),
+            ConnectionState::Disconnected => write!(f, "disconnected"),
+        }
+    }
+}
+
+/// A member of a group
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct Member {
+    pub inbox_id: String,
+    /// Wallet addresses associated with the inbox
+    pub addresses: Vec<String>,
+    pub is_admin: bool,
+    pub is_super_admin: bool,
+}
+
+/// What keys typed into the input box do
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum InputMode {
+    /// Typing a message to the focused buffer
+    #[default]
+    Message,
+    /// Typing a `/` command
+    Command,
+    /// Searching the input history with Ctrl-r
+    Search,
+}
+
+impl fmt::Display for InputMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputMode::Message => write!(f, "message"),
+            InputMode::Command => write!(f, "command"),
+            InputMode::Search => write!(f, "search"),
+        }
+    }
+}
+
+/// Part of the app an error happened in
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum ErrorSource {
+    Command,
+    XMTP,
+    View,
+}
+
+impl fmt::Display for ErrorSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorSource::Command => write!(f, "command"),
+            ErrorSource::XMTP => write!(f, "xmtp"),
+            ErrorSource::View => write!(f, "view"),
+        }
+    }
+}
+
+/// An error to show to the user
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct AppError {
+    pub source: ErrorSource,
+    /// Buffer the error is shown in
+    pub group: GroupId,
+    pub message: String,
+}
+
+impl AppError {
+    pub fn new(source: ErrorSource, group: GroupId, error: impl fmt::Display) -> Self {
+        Self { source, group, message: error.to_string() }
+    }
+}
+
+/// Screens opened over the chat
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum Page {
+    GroupInfo,
+    Identity,
+    Settings,
+}
+
+impl fmt::Display for Page {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Page::GroupInfo => write!(f, "Group info"),
+            Page::Identity => write!(f, "Identity"),
+            Page::Settings => write!(f, "Settings"),
+        }
+    }
+}
+
+/// Metadata of a group
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct GroupInfo {
+    pub id: GroupId,
+    pub name: String,
+    pub description: String,
+    /// Who may change the group, i.e `admins only`
+    pub permissions: String,
+    /// Nanoseconds since the epoch
+    pub created_at: i64,
+}
+
+/// The addresses and installations associated with our inbox
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct InboxState {
+    pub inbox_id: String,
+    pub addresses: Vec<String>,
+    pub installations: Vec<Vec<u8>>,
 }
@@ -1,11 +1,29 @@
-use std::path::PathBuf;
+use std::{
+    fmt::Write as _,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use directories::ProjectDirs;
 use lazy_static::lazy_static;
+use prost::Message as _;
+use tracing::{field::Field, Event, Subscriber};
 use tracing_error::ErrorLayer;
 use tracing_subscriber::{
-    self, fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt, Layer,
+    self,
+    filter::EnvFilter,
+    fmt::format::FmtSpan,
+    layer::{Context, SubscriberExt},
+    reload,
+    util::SubscriberInitExt,
+    Layer,
+};
+use xmtp_proto::xmtp::message_contents::EncodedContent;
+
+use crate::{
+    dispatch::{Action, ActionSender},
+    types::LOG_BUFFER,
 };
 
 lazy_static! {
@@ -14,6 +32,11 @@ lazy_static! {
         std::env::var(format!("{}_DATA", PROJECT_NAME.clone())).ok().map(PathBuf::from);
     pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
     pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
+    pub static ref HISTORY_FILE: String = format!("{}.history", env!("CARGO_PKG_NAME"));
+    pub static ref LAYOUT_FILE: String = format!("{}.layout", env!("CARGO_PKG_NAME"));
+    pub static ref CONFIG_FOLDER: Option<PathBuf> =
+        std::env::var(format!("{}_CONFIG", PROJECT_NAME.clone())).ok().map(PathBuf::from);
+    pub static ref ALIASES_FILE: String = format!("{}.aliases", env!("CARGO_PKG_NAME"));
 }
 
 pub fn project_directory() -> Option<ProjectDirs> {
@@ -31,7 +54,93 @@ pub fn get_data_dir() -> PathBuf {
     directory
 }
 
-pub fn init_logging() -> Result<()> {
+pub fn get_config_dir() -> PathBuf {
+    if let Some(s) = CONFIG_FOLDER.clone() {
+        s
+    } else if let Some(proj_dirs) = project_directory() {
+        proj_dirs.config_local_dir().to_path_buf()
+    } else {
+        PathBuf::from(".").join(".config")
+    }
+}
+
+/// Decode the text of an [`EncodedContent`] message
+pub fn decode_text(bytes: &[u8]) -> Option<String> {
+    let content = EncodedContent::decode(bytes).ok()?;
+    Some(String::from_utf8_lossy(&content.content).to_string())
+}
+
+/// Lay `rows` out under `headers` in columns padded to their widest cell, for the xchat buffer
+pub fn table<const N: usize>(headers: [&str; N], rows: &[[String; N]]) -> String {
+    let mut widths = headers.map(|header| header.chars().count());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = std::cmp::max(*width, cell.chars().count());
+        }
+    }
+    let mut table = String::new();
+    let mut push_row = |cells: &mut dyn Iterator<Item = &str>| {
+        table.push_str("\n   ");
+        for (width, cell) in widths.iter().zip(cells) {
+            let _ = write!(table, " {:<width$}", cell, width = width);
+        }
+        table.truncate(table.trim_end().len());
+    };
+    push_row(&mut headers.iter().copied());
+    for row in rows {
+        push_row(&mut row.iter().map(String::as_str));
+    }
+    table
+}
+
+/// Changes the filter of records shown in the log buffer
+type SetLogFilter = Box<dyn Fn(EnvFilter) -> Result<()> + Send + Sync>;
+
+static LOG_FILTER: OnceLock<SetLogFilter> = OnceLock::new();
+
+/// Filter of the log buffer until it is changed with `/debug`
+const DEFAULT_LOG_FILTER: &str = "warn";
+
+static LOG_DIRECTIVES: Mutex<String> = Mutex::new(String::new());
+
+/// Show records matching `directives` in the log buffer, i.e `debug` or `xchat=trace`
+pub fn set_log_filter(directives: &str) -> Result<()> {
+    let filter = EnvFilter::try_new(directives)?;
+    let set = LOG_FILTER.get().ok_or(anyhow!("Logging is not initialized"))?;
+    set(filter)?;
+    *LOG_DIRECTIVES.lock().map_err(|_| anyhow!("Log filter lock poisoned"))? = directives.into();
+    Ok(())
+}
+
+/// The filter of records shown in the log buffer
+pub fn log_filter() -> String {
+    match LOG_DIRECTIVES.lock() {
+        Ok(directives) if !directives.is_empty() => directives.clone(),
+        _ => DEFAULT_LOG_FILTER.into(),
+    }
+}
+
+/// Forwards tracing records to the log buffer
+struct BufferLayer {
+    actions: ActionSender,
+}
+
+impl<S: Subscriber> Layer<S> for BufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut text = format!("{}: ", event.metadata().target());
+        event.record(&mut |field: &Field, value: &dyn std::fmt::Debug| {
+            if field.name() == "message" {
+                let _ = write!(text, "{:?}", value);
+            } else {
+                let _ = write!(text, " {}={:?}", field.name(), value);
+            }
+        });
+        let level = event.metadata().level().to_string();
+        let _ = self.actions.send(Action::FakeMessage(vec![LOG_BUFFER], (level, text)));
+    }
+}
+
+pub fn init_logging(actions: ActionSender) -> Result<()> {
     let directory = get_data_dir();
     std::fs::create_dir_all(directory.clone())?;
     let log_path = directory.join(LOG_FILE.clone());
@@ -52,7 +161,17 @@ pub fn init_logging() -> Result<()> {
         .with_span_events(FmtSpan::CLOSE)
         .with_filter(tracing_subscriber::filter::EnvFilter::from_default_env());
 
-    tracing_subscriber::registry().with(file_subscriber).with(ErrorLayer::default()).init();
+    let (buffer_filter, handle) = reload::Layer::new(EnvFilter::new(DEFAULT_LOG_FILTER));
+    let set_filter: SetLogFilter =
+        Box::new(move |filter| handle.reload(filter).map_err(|e| anyhow!("{}", e)));
+    let _ = LOG_FILTER.set(set_filter);
+    let buffer_subscriber = BufferLayer { actions }.with_filter(buffer_filter);
+
+    tracing_subscriber::registry()
+        .with(file_subscriber)
+        .with(buffer_subscriber)
+        .with(ErrorLayer::default())
+        .init();
 
     Ok(())
 }
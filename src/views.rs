@@ -1,5 +1,12 @@
 mod chat_area;
 mod chat_rooms;
 mod input_box;
+mod nicklist;
+mod room_switcher;
+mod status_bar;
+mod toast;
 
-pub use self::{chat_area::*, chat_rooms::*, input_box::*};
+pub use self::{
+    chat_area::*, chat_rooms::*, input_box::*, nicklist::*, room_switcher::*, status_bar::*,
+    toast::*,
+};
@@ -1,21 +1,35 @@
 //! The Chat Area View
+//! Split into windows each showing a buffer, with `/window`. The layout is saved to the data
+//! directory to be restored on the next start.
 mod types;
+mod windows;
 
 use std::{future::Future, pin::Pin};
 
 use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{prelude::*, widgets::*, Frame};
 
-use self::types::*;
-use crate::dispatch::{Action, RenderContext, Store, ViewRender};
+use self::{types::*, windows::*};
+use crate::{
+    dispatch::{mark_dirty, Action, ActionSender, RenderContext, Store, ViewRender, WindowCommand},
+    util::{get_data_dir, LAYOUT_FILE},
+};
 
 #[derive(Clone)]
 pub struct ChatArea {
     messages: Messages,
+    windows: Windows,
+    events: ActionSender,
+    /// Whether a popup has taken over key presses
+    popup: bool,
 }
 
-impl Default for ChatArea {
-    fn default() -> Self {
+/// Amount of messages scrolled by one turn of the mouse wheel
+const SCROLL_STEP: usize = 3;
+
+impl ChatArea {
+    pub fn new(events: ActionSender) -> Self {
         let mut messages = Messages::default();
         messages.add(
             &vec![0],
@@ -29,29 +43,171 @@ impl Default for ChatArea {
                 ..Default::default()
             },
         );
-        messages.focused = vec![0];
+        let windows = Windows::load(&get_data_dir().join(LAYOUT_FILE.clone()));
+        // the other views start out focused on xchat
+        let _ = events.send(Action::SetFocusedGroup(windows.focused().group.clone()));
+
+        Self { messages, windows, events, popup: false }
+    }
+
+    /// Draw a new frame if a window shows the buffer `group`
+    fn mark_shown(&self, group: &[u8]) {
+        if self.windows.windows.iter().any(|window| window.group.id == group) {
+            mark_dirty();
+        }
+    }
+
+    fn save_layout(&self) {
+        if let Err(e) = self.windows.save(&get_data_dir().join(LAYOUT_FILE.clone())) {
+            log::error!("Could not save window layout {}", e);
+        }
+    }
+
+    /// Change the windows, letting the other views know which buffer is now focused
+    fn handle_window_command(&mut self, command: WindowCommand) -> Result<()> {
+        if let Err(e) = self.windows.apply(&command) {
+            let msg = e.to_string();
+            self.events.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)))?;
+            return Ok(());
+        }
+        // the layout is saved as it changes, not as the focus moves
+        if command != WindowCommand::Next {
+            self.save_layout();
+        }
+        mark_dirty();
+        self.events.send(Action::SetFocusedGroup(self.windows.focused().group.clone()))?;
+        Ok(())
+    }
+
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        if key.modifiers.intersects(KeyModifiers::ALT) && key.code == KeyCode::Char('w') {
+            self.handle_window_command(WindowCommand::Next)?;
+        }
+        Ok(())
+    }
+
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<()> {
+        let position = Position::new(mouse.column, mouse.row);
+        let Some(index) = self.windows.windows.iter().position(|w| w.area.get().contains(position))
+        else {
+            return Ok(());
+        };
+        let (_, messages) = self.messages.get(&self.windows.windows[index].group.id);
+        let window = &mut self.windows.windows[index];
+        if matches!(
+            mouse.kind,
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown | MouseEventKind::Down(_)
+        ) {
+            mark_dirty();
+        }
+        match mouse.kind {
+            MouseEventKind::ScrollUp => {
+                let max = messages.len().saturating_sub(1);
+                window.scroll = std::cmp::min(window.scroll + SCROLL_STEP, max);
+            }
+            MouseEventKind::ScrollDown => window.scroll = window.scroll.saturating_sub(SCROLL_STEP),
+            MouseEventKind::Down(MouseButton::Left) => {
+                // the first row is the border
+                let mut top = window.area.get().y + 1;
+                for (i, message) in messages.iter().enumerate().skip(window.offset.get()) {
+                    let bottom = top + Self::height(message) as u16;
+                    if (top..bottom).contains(&mouse.row) {
+                        window.selected = if window.selected == Some(i) { None } else { Some(i) };
+                        break;
+                    }
+                    top = bottom;
+                }
+                if index != self.windows.focused {
+                    self.windows.focused = index;
+                    let group = self.windows.focused().group.clone();
+                    self.events.send(Action::SetFocusedGroup(group))?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
 
-        Self { messages }
+    fn height(message: &str) -> usize {
+        std::cmp::max(message.lines().count(), 1)
+    }
+
+    /// Index of the first message to show, so that the last of `messages` is at the bottom of an
+    /// area `height` rows high
+    fn offset(messages: &[String], height: usize) -> usize {
+        let mut used = 0;
+        for (index, message) in messages.iter().enumerate().rev() {
+            used += Self::height(message);
+            if used > height {
+                return std::cmp::min(index + 1, messages.len() - 1);
+            }
+        }
+        0
     }
 }
 
 impl Store for ChatArea {
+    fn handles(&self, action: &Action) -> bool {
+        matches!(
+            action,
+            Action::FakeMessage(..)
+                | Action::ReceiveMessages(_)
+                | Action::ReceiveMessage(_)
+                | Action::Error(_)
+                | Action::SetFocusedGroup(_)
+                | Action::KeyPress(_)
+                | Action::SetPopup(_)
+                | Action::Mouse(_)
+                | Action::Window(_)
+                | Action::NewGroups(_)
+                | Action::ClearGroups
+        )
+    }
+
     fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
         let future = async {
             match action {
                 Action::FakeMessage(group_id, (user, text)) => {
+                    self.mark_shown(&group_id);
                     self.messages.add(&group_id, Message { text, user, ..Default::default() });
                 }
                 Action::ReceiveMessages(messages) => {
                     log::debug!("Received {} groups with new messages", messages.len());
-                    self.messages.add_group_messages(messages);
+                    messages.keys().for_each(|group_id| self.mark_shown(group_id));
+                    self.messages.add_group_messages(&messages);
                 }
-                Action::ReceiveMessage(msg) => self.messages.add_group_message(msg),
-                Action::SetFocusedGroup(group) => self.messages.set_focus(&group.id),
+                Action::ReceiveMessage(msg) => {
+                    self.mark_shown(&msg.group_id);
+                    self.messages.add_group_message(&msg)
+                }
+                Action::Error(error) => {
+                    let text = format!("{} failed: {}", error.source, error.message);
+                    let message = Message { user: "error".into(), text, ..Default::default() };
+                    self.mark_shown(&error.group);
+                    self.messages.add(&error.group, message);
+                }
+                Action::SetFocusedGroup(group) => {
+                    let window = self.windows.focused_mut();
+                    if window.group.id != group.id {
+                        *window = Window::new(group);
+                        mark_dirty();
+                    }
+                }
+                Action::KeyPress(key) if !self.popup => self.handle_key_event(key)?,
+                Action::SetPopup(open) => self.popup = open,
+                Action::Mouse(mouse) => self.handle_mouse_event(mouse)?,
+                Action::Window(command) => self.handle_window_command(command)?,
                 Action::NewGroups(groups) => {
                     log::debug!("Got new groups in chat area {:?}", groups);
                     self.messages.add_groups(groups);
                 }
+                Action::ClearGroups => {
+                    self.windows.clear_groups();
+                    self.messages.clear_groups();
+                    mark_dirty();
+                    self.events
+                        .send(Action::SetFocusedGroup(self.windows.focused().group.clone()))?;
+                }
                 _ => (),
             };
             Ok(())
@@ -62,23 +218,58 @@ impl Store for ChatArea {
 
 impl ViewRender for ChatArea {
     fn render(&self, frame: &mut Frame, render_ctx: RenderContext) {
-        let (users, messages) = self.messages.get();
+        let mut areas = Vec::new();
+        self.windows.layout.areas(render_ctx.area, &mut areas);
+        for (index, area) in areas {
+            // only highlight the focused window if there is more than one
+            let focused = index == self.windows.focused && self.windows.windows.len() > 1;
+            self.render_window(&self.windows.windows[index], focused, frame, area);
+        }
+    }
+}
+
+impl ChatArea {
+    fn render_window(&self, window: &Window, focused: bool, frame: &mut Frame, area: Rect) {
+        let (users, messages) = self.messages.get(&window.group.id);
         let user_style = Style::new().fg(Color::LightCyan);
         let users =
             users.into_iter().map(ListItem::new).map(|i| i.style(user_style)).collect::<Vec<_>>();
-        let messages = messages.into_iter().map(ListItem::new).collect::<Vec<_>>();
+        let end = messages.len().saturating_sub(window.scroll);
+        let offset = Self::offset(&messages[..end], area.height.saturating_sub(2) as usize);
+        let mut messages = messages.into_iter().map(ListItem::new).collect::<Vec<_>>();
+        if let Some(message) = window.selected.and_then(|s| messages.get_mut(s)) {
+            *message = message.clone().style(Style::new().bg(Color::DarkGray));
+        }
 
         let chat_area = Layout::default()
             .direction(Direction::Horizontal)
             .constraints(vec![Constraint::Percentage(7), Constraint::Percentage(93)])
-            .split(render_ctx.area);
+            .split(area);
 
-        let messages = List::new(messages)
-            .block(Block::new().borders(Borders::TOP | Borders::BOTTOM | Borders::RIGHT));
-        let users = List::new(users)
-            .block(Block::new().borders(Borders::LEFT | Borders::TOP | Borders::BOTTOM));
+        let border_style =
+            if focused { Style::new().fg(Color::LightGreen) } else { Style::default() };
+        let messages = List::new(messages).block(
+            Block::new()
+                .borders(Borders::TOP | Borders::BOTTOM | Borders::RIGHT)
+                .border_style(border_style),
+        );
+        let users = List::new(users).block(
+            Block::new()
+                .borders(Borders::LEFT | Borders::TOP | Borders::BOTTOM)
+                .border_style(border_style),
+        );
 
-        frame.render_widget(users, chat_area[0]);
-        frame.render_widget(messages, chat_area[1]);
+        frame.render_stateful_widget(
+            users,
+            chat_area[0],
+            &mut ListState::default().with_offset(offset),
+        );
+        frame.render_stateful_widget(
+            messages,
+            chat_area[1],
+            &mut ListState::default().with_offset(offset),
+        );
+        window.area.set(area);
+        window.offset.set(offset);
     }
 }
@@ -4,7 +4,7 @@ use xmtp_mls::storage::group_message::{GroupMessageKind, StoredGroupMessage};
 use xmtp_proto::xmtp::message_contents::EncodedContent;
 use prost::Message as _;
 
-use crate::types::Group;
+use crate::types::{Group, LOG_BUFFER};
 
 pub type GroupId = Vec<u8>;
 
@@ -31,16 +31,14 @@ impl Default for Message {
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Messages {
     pub inner: HashMap<GroupId, Vec<Message>>,
-    pub focused: GroupId,
 }
 
 pub const WELCOME_MESSAGE: &str = std::include_str!("../../../static/welcome_message.txt");
 
-impl Messages {
-    pub fn set_focus(&mut self, id: &GroupId) {
-        self.focused = id.clone();
-    }
+/// Records kept in the log buffer, the oldest are dropped past this
+const MAX_LOG_MESSAGES: usize = 5000;
 
+impl Messages {
     pub fn get_or_insert(&mut self, id: &GroupId) -> &mut Vec<Message> {
         if !self.inner.contains_key(id) {
             self.inner.insert(id.clone(), Default::default());
@@ -49,8 +47,11 @@ impl Messages {
         self.inner.get_mut(id).expect("Checked for existence")
     }
 
-    pub fn get(&self) -> (Vec<String>, Vec<String>) {
-        let messages = &self.inner.get(&self.focused).expect("Focused group id must always exist");
+    pub fn get(&self, id: &GroupId) -> (Vec<String>, Vec<String>) {
+        // windows restored from a previous session may show groups that do not exist yet
+        let Some(messages) = self.inner.get(id) else {
+            return Default::default();
+        };
         messages
             .iter()
             .cloned()
@@ -71,9 +72,13 @@ impl Messages {
         (0..lines).for_each(|_| message.user.push('\n'));
         let messages = self.get_or_insert(id);
         messages.push(message);
+        // dropped a tenth at a time, rather than shifting every record on each new one
+        if id.as_slice() == [LOG_BUFFER] && messages.len() > MAX_LOG_MESSAGES {
+            messages.drain(..MAX_LOG_MESSAGES / 10);
+        }
     }
 
-    pub fn add_group_message(&mut self, message: StoredGroupMessage) {
+    pub fn add_group_message(&mut self, message: &StoredGroupMessage) {
         let group_id = message.group_id.clone();
         if let Some(msgs) = self.inner.get_mut(&group_id) {
             msgs.push(Message::from(message));
@@ -82,12 +87,12 @@ impl Messages {
         }
     }
 
-    pub fn add_group_messages(&mut self, map: HashMap<GroupId, Vec<StoredGroupMessage>>) {
+    pub fn add_group_messages(&mut self, map: &HashMap<GroupId, Vec<StoredGroupMessage>>) {
         // log::debug!("Adding Messages {:#?}", map);
-        let extension = map.into_iter().map(|(id, msgs)| {
+        let extension = map.iter().map(|(id, msgs)| {
             (
-                id,
-                msgs.into_iter()
+                id.clone(),
+                msgs.iter()
                     .map(Message::from)
                     .filter(|m| matches!(m.kind, GroupMessageKind::Application))
                     .collect::<Vec<_>>(),
@@ -105,6 +110,11 @@ impl Messages {
         // log::debug!("Messages {:#?}", self.inner.values().collect::<Vec<_>>());
     }
 
+    /// Drop the messages of every group, keeping the fake buffers, whose ids are one byte long
+    pub fn clear_groups(&mut self) {
+        self.inner.retain(|id, _| id.len() == 1);
+    }
+
     pub fn add_groups(&mut self, groups: Vec<Group>) {
         let groups =
             groups.into_iter().filter(|g| !self.inner.contains_key(&g.id)).collect::<Vec<_>>();
@@ -112,12 +122,13 @@ impl Messages {
     }
 }
 
-impl From<StoredGroupMessage> for Message {
-    fn from(group_message: StoredGroupMessage) -> Message {
+// borrowed, as the messages are shared with the other views
+impl From<&StoredGroupMessage> for Message {
+    fn from(group_message: &StoredGroupMessage) -> Message {
         let content = EncodedContent::decode(group_message.decrypted_message_bytes.as_slice());
         let msg = content.unwrap();
         let text = String::from_utf8_lossy(msg.content.as_slice());
-        let user = group_message.sender_inbox_id;
+        let user = &group_message.sender_inbox_id;
         let user = format!(
             "{}...{} ",
             user.get(0..4)
@@ -0,0 +1,318 @@
+//! Windows splitting the chat area, each showing its own buffer
+use std::{cell::Cell, path::Path};
+
+use anyhow::{bail, Result};
+use ratatui::prelude::*;
+
+use crate::{dispatch::WindowCommand, types::Group};
+
+/// A window showing one buffer
+#[derive(Debug, Clone)]
+pub struct Window {
+    pub group: Group,
+    /// Amount of messages scrolled back from the newest one
+    pub scroll: usize,
+    /// Message highlighted by clicking it
+    pub selected: Option<usize>,
+    /// Area and first visible message of the last render, to map mouse events to messages
+    pub area: Cell<Rect>,
+    pub offset: Cell<usize>,
+}
+
+impl Window {
+    pub fn new(group: Group) -> Self {
+        Self { group, scroll: 0, selected: None, area: Cell::default(), offset: Cell::default() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    /// One window above the other
+    Horizontal,
+    /// Windows side by side
+    Vertical,
+}
+
+/// Tree of splits, with indexes into the windows at its leaves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pane {
+    Window(usize),
+    Split(Split, Box<Pane>, Box<Pane>),
+}
+
+impl Pane {
+    /// Area of each window when the tree is rendered to `area`
+    pub fn areas(&self, area: Rect, areas: &mut Vec<(usize, Rect)>) {
+        match self {
+            Pane::Window(index) => areas.push((*index, area)),
+            Pane::Split(split, first, second) => {
+                let direction = match split {
+                    Split::Horizontal => Direction::Vertical,
+                    Split::Vertical => Direction::Horizontal,
+                };
+                let [a, b] = Layout::default()
+                    .direction(direction)
+                    .constraints([Constraint::Fill(1), Constraint::Fill(1)])
+                    .areas(area);
+                first.areas(a, areas);
+                second.areas(b, areas);
+            }
+        }
+    }
+
+    /// Split the window at `index`, putting the window `new` after it
+    fn split(&mut self, index: usize, split: Split, new: usize) {
+        match self {
+            Pane::Window(i) if *i == index => {
+                *self =
+                    Pane::Split(split, Box::new(Pane::Window(index)), Box::new(Pane::Window(new)))
+            }
+            Pane::Window(_) => (),
+            Pane::Split(_, first, second) => {
+                first.split(index, split, new);
+                second.split(index, split, new);
+            }
+        }
+    }
+
+    /// Remove the window at `index`, giving its space to its sibling
+    fn close(&mut self, index: usize) {
+        if let Pane::Split(_, first, second) = self {
+            if **first == Pane::Window(index) {
+                *self = *second.clone();
+            } else if **second == Pane::Window(index) {
+                *self = *first.clone();
+            } else {
+                first.close(index);
+                second.close(index);
+            }
+        }
+    }
+
+    /// Shift down the indexes after the removed window `index`
+    fn renumber(&mut self, index: usize) {
+        match self {
+            Pane::Window(i) if *i > index => *i -= 1,
+            Pane::Window(_) => (),
+            Pane::Split(_, first, second) => {
+                first.renumber(index);
+                second.renumber(index);
+            }
+        }
+    }
+
+    /// Indexes of the windows in the order they are laid out, which is the order they are read in
+    fn leaves(&self, leaves: &mut Vec<usize>) {
+        match self {
+            Pane::Window(index) => leaves.push(*index),
+            Pane::Split(_, first, second) => {
+                first.leaves(leaves);
+                second.leaves(leaves);
+            }
+        }
+    }
+
+    fn write(&self, windows: &[Window], out: &mut String) {
+        match self {
+            Pane::Window(index) => {
+                // every run has a new wallet, so only the fake buffers are still there on the next
+                let group = &windows[*index].group;
+                let id = if group.is_fake() { group.id.clone() } else { vec![0] };
+                out.push_str(&format!("window {}\n", hex::encode(id)))
+            }
+            Pane::Split(split, first, second) => {
+                let split = if *split == Split::Horizontal { "h" } else { "v" };
+                out.push_str(&format!("split {}\n", split));
+                first.write(windows, out);
+                second.write(windows, out);
+            }
+        }
+    }
+
+    fn read<'a>(
+        lines: &mut impl Iterator<Item = &'a str>,
+        windows: &mut Vec<Window>,
+    ) -> Result<Pane> {
+        let Some(line) = lines.next() else { bail!("Layout ended early") };
+        match line.split_once(' ') {
+            Some(("window", id)) => {
+                // only the fake xchat and log buffers have short ids, groups of layouts saved by
+                // older versions are not ours anymore
+                let id = match hex::decode(id)?.as_slice() {
+                    [id] => *id,
+                    _ => 0,
+                };
+                windows.push(Window::new(Group::new_fake(id)));
+                Ok(Pane::Window(windows.len() - 1))
+            }
+            Some(("split", split)) => {
+                let split = if split == "h" { Split::Horizontal } else { Split::Vertical };
+                let first = Self::read(lines, windows)?;
+                let second = Self::read(lines, windows)?;
+                Ok(Pane::Split(split, Box::new(first), Box::new(second)))
+            }
+            _ => bail!("Invalid layout line {}", line),
+        }
+    }
+}
+
+/// The windows of the chat area and how they are laid out
+#[derive(Debug, Clone)]
+pub struct Windows {
+    pub windows: Vec<Window>,
+    pub layout: Pane,
+    /// index into windows
+    pub focused: usize,
+}
+
+impl Default for Windows {
+    fn default() -> Self {
+        Self { windows: vec![Window::new(Group::new_fake(0))], layout: Pane::Window(0), focused: 0 }
+    }
+}
+
+impl Windows {
+    /// Load the layout saved to `path`, or a single window showing the xchat buffer
+    pub fn load(path: &Path) -> Self {
+        let Ok(file) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        match Self::parse(&file) {
+            Ok(windows) => windows,
+            Err(e) => {
+                log::warn!("Could not load window layout {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(file: &str) -> Result<Self> {
+        let mut lines = file.lines();
+        let focused = match lines.next().and_then(|line| line.strip_prefix("focused ")) {
+            Some(focused) => focused.parse::<usize>()?,
+            None => bail!("Layout is missing the focused window"),
+        };
+        let mut windows = Vec::new();
+        let layout = Pane::read(&mut lines, &mut windows)?;
+        if focused >= windows.len() {
+            bail!("Focused window {} does not exist", focused);
+        }
+        Ok(Self { windows, layout, focused })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.write())?;
+        Ok(())
+    }
+
+    fn write(&self) -> String {
+        // windows are numbered in the order they were opened, but read back in layout order
+        let mut leaves = Vec::new();
+        self.layout.leaves(&mut leaves);
+        let focused = leaves.iter().position(|index| *index == self.focused).unwrap_or(0);
+        let mut file = format!("focused {}\n", focused);
+        self.layout.write(&self.windows, &mut file);
+        file
+    }
+
+    pub fn focused(&self) -> &Window {
+        &self.windows[self.focused]
+    }
+
+    pub fn focused_mut(&mut self) -> &mut Window {
+        &mut self.windows[self.focused]
+    }
+
+    /// Show the xchat buffer in the windows showing a group, keeping the layout
+    pub fn clear_groups(&mut self) {
+        for window in &mut self.windows {
+            if !window.group.is_fake() {
+                *window = Window::new(Group::new_fake(0));
+            }
+        }
+    }
+
+    /// Split, close or move focus away from the focused window
+    pub fn apply(&mut self, command: &WindowCommand) -> Result<()> {
+        match command {
+            WindowCommand::SplitHorizontal | WindowCommand::SplitVertical => {
+                let split = if *command == WindowCommand::SplitHorizontal {
+                    Split::Horizontal
+                } else {
+                    Split::Vertical
+                };
+                self.windows.push(Window::new(self.focused().group.clone()));
+                let new = self.windows.len() - 1;
+                self.layout.split(self.focused, split, new);
+                self.focused = new;
+            }
+            WindowCommand::Close => {
+                if self.windows.len() == 1 {
+                    bail!("Cannot close the only window");
+                }
+                self.layout.close(self.focused);
+                self.layout.renumber(self.focused);
+                self.windows.remove(self.focused);
+                self.focused = self.focused.saturating_sub(1);
+            }
+            WindowCommand::Next => self.focused = (self.focused + 1) % self.windows.len(),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{GroupId, LOG_BUFFER};
+
+    fn ids(windows: &Windows) -> Vec<GroupId> {
+        windows.windows.iter().map(|window| window.group.id.clone()).collect()
+    }
+
+    #[test]
+    fn layout_round_trip() {
+        let mut windows = Windows::default();
+        windows.apply(&WindowCommand::SplitVertical).unwrap();
+        windows.focused_mut().group = Group::new_fake(LOG_BUFFER);
+        windows.apply(&WindowCommand::SplitHorizontal).unwrap();
+        windows.focused_mut().group = Group::new(vec![7; 16], 0, 0);
+        windows.apply(&WindowCommand::Next).unwrap();
+
+        let read = Windows::parse(&windows.write()).unwrap();
+        assert_eq!(read.layout, windows.layout);
+        assert_eq!(read.focused, windows.focused);
+        // the group is gone with the wallet of this run
+        assert_eq!(ids(&read), vec![vec![0], vec![LOG_BUFFER], vec![0]]);
+    }
+
+    #[test]
+    fn focus_is_saved_in_layout_order() {
+        let mut windows = Windows::default();
+        windows.apply(&WindowCommand::SplitVertical).unwrap();
+        windows.apply(&WindowCommand::SplitVertical).unwrap();
+        // window 0 splits again, so its new sibling comes before window 1 in the layout
+        windows.focused = 0;
+        windows.apply(&WindowCommand::SplitHorizontal).unwrap();
+        windows.focused_mut().group = Group::new_fake(LOG_BUFFER);
+
+        let read = Windows::parse(&windows.write()).unwrap();
+        assert_eq!(read.focused().group.id, vec![LOG_BUFFER]);
+        assert_eq!(read.windows.len(), 4);
+    }
+
+    #[test]
+    fn groups_of_old_layouts_show_xchat() {
+        let file = format!("focused 1\nsplit v\nwindow 01\nwindow {}\n", hex::encode([7; 16]));
+        let read = Windows::parse(&file).unwrap();
+        assert_eq!(ids(&read), vec![vec![LOG_BUFFER], vec![0]]);
+        assert_eq!(read.focused, 1);
+    }
+
+    #[test]
+    fn invalid_layouts_are_rejected() {
+        assert!(Windows::parse("split v\nwindow 00\nwindow 00\n").is_err());
+        assert!(Windows::parse("focused 0\nsplit v\nwindow 00\n").is_err());
+        assert!(Windows::parse("focused 2\nwindow 00\n").is_err());
+    }
+}
@@ -1,14 +1,23 @@
 //! Chat Rooms View
-use std::{future::Future, pin::Pin};
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    future::Future,
+    pin::Pin,
+};
 
-use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{prelude::*, widgets::*, Frame};
-use tokio::sync::broadcast::Sender;
+use tokio::sync::mpsc::Sender;
+use xmtp_mls::storage::group_message::{GroupMessageKind, StoredGroupMessage};
 
 use crate::{
-    dispatch::{Action, RenderContext, Store, ViewRender},
-    types::{Group, GroupIdWrapper},
+    dispatch::{
+        mark_dirty, Action, ActionSender, ListCommand, RenderContext, Store, ViewRender, XMTPAction,
+    },
+    types::{Activity, Group, GroupId, GroupIdWrapper, HotlistEntry, Identity, LOG_BUFFER},
+    util::{decode_text, table},
 };
 
 #[derive(Debug, Clone)]
@@ -17,58 +26,359 @@ pub struct ChatRooms {
     groups: Vec<Group>,
     /// index into groups vector
     focused: usize,
-    events: Sender<Action>,
+    events: ActionSender,
+    xmtp: Sender<XMTPAction>,
+    /// Width set by dragging the right border with the mouse
+    width: Option<u16>,
+    resizing: bool,
+    /// Area the rooms were last rendered to
+    area: Cell<Rect>,
+    /// First room shown in the last render, the list scrolls to keep the focused room visible
+    offset: Cell<usize>,
+    /// Whether a popup has taken over key presses
+    popup: bool,
+    /// Unread activity in buffers that are not focused
+    hotlist: HashMap<GroupId, Activity>,
+    /// Amount of messages received in each buffer since it was last focused
+    unread: HashMap<GroupId, usize>,
+    /// When the last message of each group was sent, in nanoseconds
+    last_activity: HashMap<GroupId, i64>,
+    /// Names given to the groups by their creators
+    names: HashMap<GroupId, String>,
+    /// Users that have sent messages in each group, for groups whose members are not known yet
+    senders: HashMap<GroupId, HashSet<String>>,
+    /// Amount of members in each group, to tell private conversations apart
+    members: HashMap<GroupId, usize>,
+    me: Option<Identity>,
 }
 
+/// Narrowest the rooms pane can be dragged to, including borders
+const MIN_WIDTH: u16 = 5;
+
 // TODO: Search `.unwrap`, `.expect`, `let _ =`
 
 impl ChatRooms {
-    pub fn new(events: Sender<Action>) -> Self {
-        Self { rooms: vec!["xchat".into()], groups: vec![Group::new_fake(0)], focused: 0, events }
+    pub fn new(events: ActionSender, xmtp: Sender<XMTPAction>) -> Self {
+        Self {
+            rooms: vec!["xchat".into(), "log".into()],
+            groups: vec![Group::new_fake(0), Group::new_fake(LOG_BUFFER)],
+            focused: 0,
+            events,
+            xmtp,
+            width: None,
+            resizing: false,
+            area: Cell::default(),
+            offset: Cell::default(),
+            popup: false,
+            hotlist: HashMap::new(),
+            unread: HashMap::new(),
+            last_activity: HashMap::new(),
+            names: HashMap::new(),
+            senders: HashMap::new(),
+            members: HashMap::new(),
+            me: None,
+        }
     }
 
-    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
-        if key.modifiers.intersects(KeyModifiers::CONTROL) {
-            match key.code {
-                KeyCode::Char('n') => {
-                    self.focus_next();
-                    self.events.send(Action::SetFocusedGroup(self.groups[self.focused].clone()))?;
-                }
-                KeyCode::Char('p') => {
-                    self.focus_previous();
-                    self.events.send(Action::SetFocusedGroup(self.groups[self.focused].clone()))?;
-                }
-                _ => (),
-            };
+    /// Focus the buffer at `index`, marking its activity as read
+    fn focus(&mut self, index: usize) -> Result<()> {
+        if let Some(group) = self.groups.get(index) {
+            self.focused = index;
+            self.events.send(Action::SetFocusedGroup(group.clone()))?;
         }
         Ok(())
     }
 
-    fn focus_next(&mut self) {
-        if self.focused < (self.groups.len() - 1) {
-            self.focused += 1;
+    /// Name a group is shown with, the name its creator gave it once its info arrived
+    fn room_name(&self, group: &Group) -> String {
+        match self.names.get(&group.id) {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => format!("{}", GroupIdWrapper::from(group.id.clone())),
         }
     }
 
-    fn focus_previous(&mut self) {
-        self.focused = self.focused.saturating_sub(1);
+    /// Index of the buffer whose name matches `name`, preferring exact matches
+    fn find(&self, name: &str) -> Option<usize> {
+        self.rooms
+            .iter()
+            .position(|room| room.eq_ignore_ascii_case(name))
+            .or_else(|| self.rooms.iter().position(|room| room.contains(name)))
+            .or_else(|| self.groups.iter().position(|g| hex::encode(&g.id).starts_with(name)))
+    }
+
+    /// Focus the buffer whose name matches `name`, preferring exact matches
+    fn focus_by_name(&mut self, name: &str) -> Result<()> {
+        match self.find(name) {
+            Some(index) => self.focus(index),
+            None => {
+                let msg = format!("No buffer named {}", name);
+                self.events.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)))?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Send `text` to the buffer with the number or name `buffer`
+    async fn send_to(&mut self, buffer: &str, text: String) -> Result<()> {
+        let index = match buffer.parse::<usize>() {
+            Ok(number) => number.checked_sub(1).filter(|index| *index < self.groups.len()),
+            Err(_) => self.find(buffer),
+        };
+        let Some(index) = index else {
+            bail!("No buffer named {}", buffer);
+        };
+        self.xmtp.send(XMTPAction::SendMessage(self.groups[index].clone(), text)).await?;
+        Ok(())
+    }
+
+    /// Focus the buffer with the most important activity, the lowest numbered first
+    fn focus_next_active(&mut self) -> Result<()> {
+        let next = self
+            .groups
+            .iter()
+            .enumerate()
+            .filter_map(|(index, g)| self.hotlist.get(&g.id).map(|activity| (index, *activity)))
+            .max_by(|(a, a_activity), (b, b_activity)| a_activity.cmp(b_activity).then(b.cmp(a)));
+        if let Some((index, _)) = next {
+            self.focus(index)?;
+        }
+        Ok(())
+    }
+
+    fn add_activity(&mut self, msg: &StoredGroupMessage) -> Result<()> {
+        let is_mine = self.me.as_ref().is_some_and(|me| me.inbox_id == msg.sender_inbox_id);
+        let senders = self.senders.entry(msg.group_id.clone()).or_default();
+        if !is_mine {
+            senders.insert(msg.sender_inbox_id.clone());
+        }
+        if msg.kind == GroupMessageKind::Application {
+            let last = self.last_activity.entry(msg.group_id.clone()).or_default();
+            *last = std::cmp::max(*last, msg.sent_at_ns);
+        }
+        let is_focused = self.groups[self.focused].id == msg.group_id;
+        if is_mine || is_focused || msg.kind != GroupMessageKind::Application {
+            return Ok(());
+        }
+        *self.unread.entry(msg.group_id.clone()).or_default() += 1;
+
+        let text = decode_text(&msg.decrypted_message_bytes).unwrap_or_default();
+        let is_mention = self.me.as_ref().is_some_and(|me| {
+            text.contains(&me.inbox_id) || text.to_lowercase().contains(&me.address.to_lowercase())
+        });
+        let is_private = match self.members.get(&msg.group_id) {
+            Some(members) => *members == 2,
+            None => senders.len() == 1,
+        };
+        let activity = if is_private {
+            Activity::Private
+        } else if is_mention {
+            Activity::Mention
+        } else {
+            Activity::Message
+        };
+        let current = self.hotlist.entry(msg.group_id.clone()).or_insert(activity);
+        *current = std::cmp::max(*current, activity);
+        self.send_hotlist()
+    }
+
+    /// Let the status bar know about the activity the rooms are colored by
+    fn send_hotlist(&self) -> Result<()> {
+        mark_dirty();
+        let hotlist = self
+            .groups
+            .iter()
+            .zip(self.rooms.iter())
+            .enumerate()
+            .filter_map(|(index, (group, name))| {
+                self.hotlist.get(&group.id).map(|activity| HotlistEntry {
+                    number: index + 1,
+                    name: name.clone(),
+                    activity: *activity,
+                })
+            })
+            .collect();
+        self.events.send(Action::Hotlist(hotlist))?;
+        Ok(())
+    }
+
+    /// Keep only the fake buffers, focusing xchat if a group was focused
+    fn clear_groups(&mut self) -> Result<()> {
+        let fake = self.groups.iter().take_while(|group| group.is_fake()).count();
+        self.groups.truncate(fake);
+        self.rooms.truncate(fake);
+        self.hotlist.retain(|id, _| self.groups.iter().any(|group| &group.id == id));
+        self.unread.clear();
+        self.last_activity.clear();
+        self.names.clear();
+        self.senders.clear();
+        self.members.clear();
+        if self.focused >= fake {
+            self.focus(0)?;
+        }
+        self.send_hotlist()
+    }
+
+    /// Print every group with its members, last message and unread messages in the xchat buffer
+    fn list_groups(&self) -> Result<()> {
+        let rows = self
+            .groups
+            .iter()
+            .enumerate()
+            .filter(|(_, group)| !group.is_fake())
+            .map(|(index, group)| {
+                let members = self.members.get(&group.id).map(usize::to_string);
+                let last_activity = self.last_activity.get(&group.id).map(|ns| {
+                    chrono::DateTime::from_timestamp_nanos(*ns)
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                });
+                [
+                    (index + 1).to_string(),
+                    self.names.get(&group.id).cloned().unwrap_or_default(),
+                    hex::encode(&group.id),
+                    members.unwrap_or_else(|| "?".into()),
+                    last_activity.unwrap_or_else(|| "-".into()),
+                    self.unread.get(&group.id).copied().unwrap_or(0).to_string(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let msg = match rows.len() {
+            0 => "Not in any group, `/create` one or `/join` one you were invited to".to_string(),
+            n => {
+                let headers = ["#", "Name", "Id", "Members", "Last activity", "Unread"];
+                format!("{} groups{}", n, table(headers, &rows))
+            }
+        };
+        self.events.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)))?;
+        Ok(())
+    }
+
+    /// Width of the pane, if it was resized with the mouse
+    pub fn width(&self) -> Option<u16> {
+        self.width
+    }
+
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<()> {
+        let area = self.area.get();
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left)
+                if mouse.column + 1 == area.right()
+                    && (area.top()..area.bottom()).contains(&mouse.row) =>
+            {
+                self.resizing = true;
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.resizing => {
+                let width = (mouse.column + 1).saturating_sub(area.x);
+                self.width = Some(std::cmp::max(width, MIN_WIDTH));
+                mark_dirty();
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.resizing = false,
+            MouseEventKind::Down(MouseButton::Left)
+                if area.contains(Position::new(mouse.column, mouse.row)) =>
+            {
+                // the first row is the border
+                let row = (mouse.row - area.y).checked_sub(1).map(usize::from);
+                if let Some(index) = row.map(|row| row + self.offset.get()) {
+                    self.focus(index)?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        if key.modifiers.intersects(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('n') => self.focus(self.focused + 1)?,
+                KeyCode::Char('p') => self.focus(self.focused.saturating_sub(1))?,
+                _ => (),
+            };
+        } else if key.modifiers.intersects(KeyModifiers::ALT) {
+            match key.code {
+                KeyCode::Char(c @ '1'..='9') => self.focus(c as usize - '1' as usize)?,
+                KeyCode::Char('a') => self.focus_next_active()?,
+                _ => (),
+            };
+        }
+        Ok(())
     }
 }
 
 impl Store for ChatRooms {
+    fn handles(&self, action: &Action) -> bool {
+        matches!(
+            action,
+            Action::KeyPress(_)
+                | Action::SetPopup(_)
+                | Action::SetFocusedGroup(_)
+                | Action::ChangeRoom(_)
+                | Action::ChangeRoomByName(_)
+                | Action::SendToBuffer(..)
+                | Action::Identity(_)
+                | Action::GroupMembers(..)
+                | Action::GroupInfo(_)
+                | Action::List(ListCommand::Group)
+                | Action::ReceiveMessage(_)
+                | Action::ReceiveMessages(_)
+                | Action::Mouse(_)
+                | Action::NewGroups(_)
+                | Action::ClearGroups
+        )
+    }
+
     fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
         let future = async move {
             match action {
-                Action::KeyPress(key) => self.handle_key_event(key).await?,
+                Action::KeyPress(key) if !self.popup => self.handle_key_event(key).await?,
+                Action::SetPopup(open) => self.popup = open,
+                Action::SetFocusedGroup(group) => {
+                    if let Some(index) = self.groups.iter().position(|g| g.id == group.id) {
+                        self.focused = index;
+                        mark_dirty();
+                    }
+                    self.unread.remove(&group.id);
+                    if self.hotlist.remove(&group.id).is_some() {
+                        self.send_hotlist()?;
+                    }
+                }
+                Action::ChangeRoom(index) => self.focus(index)?,
+                Action::ChangeRoomByName(name) => self.focus_by_name(&name)?,
+                Action::SendToBuffer(buffer, text) => self.send_to(&buffer, text).await?,
+                Action::Identity(identity) => self.me = Some(identity),
+                Action::GroupMembers(group_id, members) => {
+                    self.members.insert(group_id, members.len());
+                }
+                Action::GroupInfo(info) => {
+                    self.names.insert(info.id.clone(), info.name);
+                    if let Some(index) = self.groups.iter().position(|g| g.id == info.id) {
+                        let name = self.room_name(&self.groups[index]);
+                        if self.rooms[index] != name {
+                            self.rooms[index] = name;
+                            self.send_hotlist()?;
+                        }
+                    }
+                }
+                Action::List(ListCommand::Group) => self.list_groups()?,
+                Action::ReceiveMessage(msg) => self.add_activity(&msg)?,
+                Action::ReceiveMessages(messages) => {
+                    for msg in messages.values().flatten() {
+                        self.add_activity(msg)?;
+                    }
+                }
+                Action::Mouse(mouse) => self.handle_mouse_event(mouse)?,
                 Action::NewGroups(groups) => {
                     log::debug!("Got new groups in chat rooms {:?}", groups);
                     let groups =
                         groups.into_iter().filter(|g| !self.groups.contains(g)).collect::<Vec<_>>();
                     self.groups.extend(groups.clone());
                     for group in groups {
-                        self.rooms.push(format!("{}", &GroupIdWrapper::from(group.id)));
+                        self.rooms.push(self.room_name(&group));
+                        mark_dirty();
                     }
                 }
+                Action::ClearGroups => self.clear_groups()?,
                 _ => (),
             };
             Ok(())
@@ -79,12 +389,38 @@ impl Store for ChatRooms {
 
 impl ViewRender for ChatRooms {
     fn render(&self, frame: &mut Frame, render_ctx: RenderContext) {
-        let mut rooms = self.rooms.iter().cloned().map(ListItem::new).collect::<Vec<_>>();
+        let mut rooms = self
+            .rooms
+            .iter()
+            .zip(self.groups.iter())
+            .enumerate()
+            .map(|(index, (room, group))| {
+                let item = ListItem::new(format!("{}. {}", index + 1, room));
+                match self.hotlist.get(&group.id) {
+                    Some(activity) => item.style(activity_style(*activity)),
+                    None => item,
+                }
+            })
+            .collect::<Vec<_>>();
         rooms[self.focused] = rooms[self.focused].clone().style(Style::new().fg(Color::LightGreen));
 
-        frame.render_widget(
+        let mut state =
+            ListState::default().with_offset(self.offset.get()).with_selected(Some(self.focused));
+        frame.render_stateful_widget(
             List::new(rooms).block(Block::new().borders(Borders::ALL)),
             render_ctx.area,
+            &mut state,
         );
+        self.area.set(render_ctx.area);
+        self.offset.set(state.offset());
+    }
+}
+
+/// Color of a buffer in the rooms and hotlist, by its activity
+pub fn activity_style(activity: Activity) -> Style {
+    match activity {
+        Activity::Message => Style::new().fg(Color::White).add_modifier(Modifier::BOLD),
+        Activity::Mention => Style::new().fg(Color::LightMagenta).add_modifier(Modifier::BOLD),
+        Activity::Private => Style::new().fg(Color::LightYellow).add_modifier(Modifier::BOLD),
     }
 }
@@ -1,7 +1,11 @@
-use std::{future::Future, pin::Pin};
+//! The Input Box View
+mod completion;
+mod history;
+
+use std::{fs::OpenOptions, future::Future, io::Write as _, pin::Pin};
 
 use anyhow::Result;
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     widgets::{Block, Borders},
     Frame,
@@ -9,9 +13,14 @@ use ratatui::{
 use tokio::sync::mpsc::Sender;
 use tui_textarea::{CursorMove, Input, Key, TextArea};
 
+use self::{completion::*, history::*};
 use crate::{
-    dispatch::{Action, CommandAction, RenderContext, Store, ViewRender, XMTPAction},
-    types::Group,
+    dispatch::{
+        mark_dirty, Action, ActionSender, CommandAction, RenderContext, Store, ViewRender,
+        XMTPAction,
+    },
+    types::{AppError, ErrorSource, Group, InputMode},
+    util::{get_data_dir, HISTORY_FILE},
 };
 
 #[derive(Debug, Clone)]
@@ -19,30 +28,89 @@ pub struct InputBox<'a> {
     text: String,
     xmtp: Sender<XMTPAction>,
     command: Sender<CommandAction>,
+    events: ActionSender,
     focused_group: Group,
     text_area: TextArea<'a>,
+    history: History,
+    search: Option<ReverseSearch>,
+    completer: Completer,
+    /// Whether a popup has taken over key presses
+    popup: bool,
+    /// Mode last shown in the status bar
+    mode: InputMode,
 }
 
 impl<'a> InputBox<'a> {
-    pub fn new(xmtp: Sender<XMTPAction>, command: Sender<CommandAction>) -> Self {
+    pub fn new(
+        xmtp: Sender<XMTPAction>,
+        command: Sender<CommandAction>,
+        events: ActionSender,
+    ) -> Self {
         let text_area = Self::text_area();
-        Self { text: "".into(), xmtp, command, focused_group: Group::new_fake(0), text_area }
+        let history = History::load(get_data_dir().join(HISTORY_FILE.clone()));
+        Self {
+            text: "".into(),
+            xmtp,
+            command,
+            events,
+            focused_group: Group::new_fake(0),
+            text_area,
+            history,
+            search: None,
+            completer: Completer::default(),
+            popup: false,
+            mode: InputMode::default(),
+        }
     }
 
-    // TODO: Find a way NOT to recreate the textarea to preserve text history
     fn text_area() -> TextArea<'a> {
         let mut text_area = TextArea::from(Vec::<String>::new());
         text_area.set_block(Block::default().borders(Borders::ALL));
         text_area
     }
 
+    /// Replace the contents of the input box, leaving the cursor at the end of the text
+    fn set_text(&mut self, text: &str) {
+        self.text_area = Self::text_area();
+        self.text_area.insert_str(text);
+        self.set_search_title();
+    }
+
+    fn set_search_title(&mut self) {
+        let block = Block::default().borders(Borders::ALL);
+        let block = match &self.search {
+            Some(ReverseSearch { query, found: None, .. }) if !query.is_empty() => {
+                block.title(format!("(failed reverse-i-search)`{}'", query))
+            }
+            Some(ReverseSearch { query, .. }) => {
+                block.title(format!("(reverse-i-search)`{}'", query))
+            }
+            None => block,
+        };
+        self.text_area.set_block(block);
+    }
+
     async fn handle_enter(&mut self) -> Result<()> {
         if self.text_area.lines()[0].starts_with("/") {
             let text_area = std::mem::replace(&mut self.text_area, Self::text_area());
             let command = text_area.into_lines().remove(0);
             log::debug!("Got a command {}", &command);
+            self.history.push(&self.focused_group.id, command.clone());
             let cmd = command.strip_prefix("/").expect("Checked if start with `/`");
-            let cmd = CommandAction::from_string(cmd.into(), &self.focused_group)?;
+            let cmd = match CommandAction::from_string(cmd.into(), &self.focused_group) {
+                Ok(cmd) => cmd,
+                Err(e) => {
+                    let group = self.focused_group.id.clone();
+                    let error = AppError::new(ErrorSource::Command, group, e);
+                    self.events.send(Action::Error(error))?;
+                    return Ok(());
+                }
+            };
+            match &cmd {
+                CommandAction::Invite(_, user) => self.completer.add_contact(user),
+                CommandAction::Compose(text) => return self.compose(text),
+                _ => (),
+            }
             self.command.send(cmd).await?;
             self.text.clear();
         } else {
@@ -53,30 +121,72 @@ impl<'a> InputBox<'a> {
             self.text_area.move_cursor(CursorMove::Jump(0, 0));
             self.text_area.delete_line_by_head();
             let mut lines = text_area.into_lines();
-            lines.join("\n");
+            self.history.push(&self.focused_group.id, lines.join("\n"));
             self.xmtp
                 .send(
                     XMTPAction::SendMessage(self.focused_group.clone(), lines.drain(..).collect())
                         .into(),
                 )
-                .await
-                .expect("Handle bad send");
+                .await?;
         }
 
         Ok(())
     }
 
-    // TODO:
-    // Crossterm does not recognize Shift + Enter
-    // which is annoying. Need another keybinding or figure out a workaround
-    //
+    /// Paste text verbatim, without submitting it on newlines
+    fn handle_paste(&mut self, text: String) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        self.text_area.insert_str(text);
+    }
+
+    // Shift + Enter is only reported by terminals supporting the kitty keyboard protocol,
+    // so Ctrl + Enter inserts a newline as well.
     async fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        if self.search.is_some() && self.handle_search_key(key.into()) {
+            return Ok(());
+        }
+
+        match key.code {
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.complete(key.code == KeyCode::BackTab);
+                return Ok(());
+            }
+            _ => self.completer.reset(),
+        }
+
+        let (row, _) = self.text_area.cursor();
+        let last_row = self.text_area.lines().len().saturating_sub(1);
         match key.into() {
-            Input { key: Key::Enter, ctrl: true, .. } => {
+            // opens the room switcher
+            Input { key: Key::Char('k'), ctrl: true, alt: false, .. } => (),
+            Input { key: Key::Char('e'), ctrl: false, alt: true, .. } => {
+                let text = self.text_area.lines().join("\n");
+                self.compose(&text)?;
+            }
+            Input { key: Key::Char('r'), ctrl: true, alt: false, .. } => {
+                let draft = self.text_area.lines().join("\n");
+                self.search = Some(ReverseSearch { draft, ..Default::default() });
+                self.set_search_title();
+            }
+            Input { key: Key::Up, ctrl: false, alt, .. } if alt || row == 0 => {
+                let ring = if alt { Ring::Global } else { Ring::Buffer };
+                let current = self.text_area.lines().join("\n");
+                if let Some(entry) = self.history.previous(ring, &self.focused_group.id, &current) {
+                    self.set_text(&entry);
+                }
+            }
+            Input { key: Key::Down, ctrl: false, alt, .. } if alt || row == last_row => {
+                let ring = if alt { Ring::Global } else { Ring::Buffer };
+                if let Some(entry) = self.history.next(ring, &self.focused_group.id) {
+                    self.set_text(&entry);
+                }
+            }
+            Input { key: Key::Enter, ctrl: true, .. }
+            | Input { key: Key::Enter, shift: true, .. } => {
                 self.text_area.move_cursor(CursorMove::End);
                 self.text_area.insert_newline()
             }
-            Input { key: Key::Enter, ctrl: false, .. } => {
+            Input { key: Key::Enter, ctrl: false, shift: false, .. } => {
                 // we want to create a newline here.
                 self.handle_enter().await?;
             }
@@ -87,17 +197,154 @@ impl<'a> InputBox<'a> {
         Ok(())
     }
 
+    /// Edit `text` in `$EDITOR`. The terminal runs the editor, sending back what was written as
+    /// [`Action::Composed`].
+    /// The draft is written to a new file only we can read, so it can not be swapped for a link
+    /// to another file or read by other users.
+    fn compose(&mut self, text: &str) -> Result<()> {
+        let directory = get_data_dir();
+        std::fs::create_dir_all(&directory)?;
+        let nanos = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        let path = directory.join(format!("compose-{}-{}.txt", std::process::id(), nanos));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(&path)?.write_all(text.as_bytes())?;
+        self.events.send(Action::RunEditor(path))?;
+        Ok(())
+    }
+
+    fn complete(&mut self, reverse: bool) {
+        let (row, col) = self.text_area.cursor();
+        let line = self.text_area.lines()[row].chars().take(col).collect::<String>();
+        if let Some(Completion { delete, insert }) =
+            self.completer.complete(&line, &self.focused_group.id, reverse)
+        {
+            (0..delete).for_each(|_| {
+                self.text_area.delete_char();
+            });
+            self.text_area.insert_str(insert);
+        }
+    }
+
+    /// Handle a key while reverse searching through the history. Returns false if the key ended
+    /// the search and should be handled as regular input.
+    fn handle_search_key(&mut self, input: Input) -> bool {
+        let Some(mut search) = self.search.take() else {
+            return false;
+        };
+        let from = match input {
+            Input { key: Key::Char('r'), ctrl: true, alt: false, .. } => {
+                search.found.and_then(|i| i.checked_sub(1))
+            }
+            Input { key: Key::Char('g'), ctrl: true, alt: false, .. }
+            | Input { key: Key::Esc, .. } => {
+                self.set_text(&search.draft);
+                return true;
+            }
+            Input { key: Key::Backspace, .. } => {
+                search.query.pop();
+                Some(self.history.newest())
+            }
+            Input { key: Key::Char(c), ctrl: false, alt: false, .. } => {
+                search.query.push(c);
+                Some(search.found.unwrap_or(self.history.newest()))
+            }
+            Input { key: Key::Null, .. } => {
+                self.search = Some(search);
+                return true;
+            }
+            _ => {
+                self.set_search_title();
+                return false;
+            }
+        };
+
+        if let Some(found) = from.and_then(|from| self.history.search_back(&search.query, from)) {
+            search.found = Some(found);
+            let entry = self.history.get(found).unwrap_or_default().to_string();
+            self.search = Some(search);
+            self.set_text(&entry);
+        } else {
+            search.found = None;
+            self.search = Some(search);
+            self.set_search_title();
+        }
+        true
+    }
+
+    /// Let the status bar know if the mode changed with the last input
+    fn send_mode(&mut self) -> Result<()> {
+        let mode = if self.search.is_some() {
+            InputMode::Search
+        } else if self.text_area.lines().first().is_some_and(|line| line.starts_with('/')) {
+            InputMode::Command
+        } else {
+            InputMode::Message
+        };
+        if mode != self.mode {
+            self.mode = mode;
+            self.events.send(Action::InputMode(mode))?;
+        }
+        Ok(())
+    }
+
     pub fn lines(&'a self) -> &'a [String] {
         self.text_area.lines()
     }
 }
 
 impl<'a> Store for InputBox<'a> {
+    fn handles(&self, action: &Action) -> bool {
+        matches!(
+            action,
+            Action::KeyPress(_)
+                | Action::Paste(_)
+                | Action::SetPopup(_)
+                | Action::SetFocusedGroup(_)
+                | Action::Composed(_)
+                | Action::ReceiveMessage(_)
+                | Action::ReceiveMessages(_)
+                | Action::GroupMembers(..)
+                | Action::ClearGroups
+        )
+    }
+
     fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
         let future = async move {
             match action {
-                Action::KeyPress(key) => self.handle_key_event(key).await?,
+                Action::KeyPress(key) if !self.popup => {
+                    self.handle_key_event(key).await?;
+                    self.send_mode()?;
+                    mark_dirty();
+                }
+                Action::Paste(text) if !self.popup => {
+                    self.handle_paste(text);
+                    self.send_mode()?;
+                    mark_dirty();
+                }
+                Action::SetPopup(open) => self.popup = open,
                 Action::SetFocusedGroup(group) => self.focused_group = group,
+                Action::Composed(text) => {
+                    self.set_text(text.trim_end_matches(['\n', '\r']));
+                    mark_dirty();
+                }
+                Action::ReceiveMessage(msg) => {
+                    self.completer.add_member(&msg.group_id, &msg.sender_inbox_id)
+                }
+                Action::ReceiveMessages(messages) => {
+                    for msg in messages.values().flatten() {
+                        self.completer.add_member(&msg.group_id, &msg.sender_inbox_id);
+                    }
+                }
+                Action::GroupMembers(group_id, members) => {
+                    for member in members {
+                        self.completer.add_member(&group_id, &member.inbox_id);
+                        member.addresses.iter().for_each(|a| self.completer.add_contact(a));
+                    }
+                }
+                Action::ClearGroups => self.completer.clear(),
                 _ => (),
             };
             Ok(())
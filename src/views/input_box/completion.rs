@@ -0,0 +1,110 @@
+//! Tab completion for the Input Box.
+//! Completes command names, command arguments and the users of the focused group, cycling through
+//! the candidates on repeated presses of Tab.
+use std::collections::HashMap;
+
+use crate::{dispatch::CommandAction, types::GroupId};
+
+#[derive(Debug, Clone, Default)]
+pub struct Completer {
+    /// Inbox ids of the users in each group, completed for mentions
+    members: HashMap<GroupId, Vec<String>>,
+    /// Wallet addresses of every user we have seen or invited, across all groups, completed for
+    /// `/invite` as it takes an address
+    contacts: Vec<String>,
+    cycle: Option<Cycle>,
+}
+
+/// Candidates being cycled through by repeated completions
+#[derive(Debug, Clone)]
+struct Cycle {
+    candidates: Vec<String>,
+    index: usize,
+}
+
+/// Replace the `delete` characters before the cursor with `insert`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub delete: usize,
+    pub insert: String,
+}
+
+impl Completer {
+    pub fn add_member(&mut self, group: &GroupId, user: &str) {
+        let members = self.members.entry(group.clone()).or_default();
+        if !members.iter().any(|m| m == user) {
+            members.push(user.to_string());
+        }
+    }
+
+    pub fn add_contact(&mut self, address: &str) {
+        if !self.contacts.iter().any(|c| c == address) {
+            self.contacts.push(address.to_string());
+        }
+    }
+
+    /// Forget the users seen with the previous identity
+    pub fn clear(&mut self) {
+        self.members.clear();
+        self.contacts.clear();
+        self.cycle = None;
+    }
+
+    /// Stop cycling through candidates. The next completion starts from the text in the input box.
+    pub fn reset(&mut self) {
+        self.cycle = None;
+    }
+
+    /// Complete the word before the cursor. `line` is the text of the current line up to the
+    /// cursor. Cycles backwards through the candidates if `reverse` is set.
+    pub fn complete(&mut self, line: &str, group: &GroupId, reverse: bool) -> Option<Completion> {
+        if let Some(cycle) = &mut self.cycle {
+            let delete = cycle.candidates[cycle.index].chars().count();
+            let len = cycle.candidates.len();
+            cycle.index =
+                if reverse { (cycle.index + len - 1) % len } else { (cycle.index + 1) % len };
+            return Some(Completion { delete, insert: cycle.candidates[cycle.index].clone() });
+        }
+
+        let word = line.rsplit(char::is_whitespace).next().unwrap_or_default();
+        let preceding = line[..line.len() - word.len()].split_whitespace().collect::<Vec<_>>();
+        let candidates = match preceding.as_slice() {
+            [] if word.starts_with('/') => {
+                CommandAction::names().map(|c| format!("/{}", c)).collect()
+            }
+            [command] if command.starts_with('/') => {
+                let command = command.trim_start_matches('/');
+                match CommandAction::arguments(command) {
+                    [] if command == "invite" => self.contacts.clone(),
+                    arguments => arguments.iter().map(ToString::to_string).collect(),
+                }
+            }
+            [command, ..] if command.starts_with('/') && word.starts_with('-') => {
+                CommandAction::flags(command.trim_start_matches('/'))
+            }
+            [command, ..] if command.starts_with('/') => Vec::new(),
+            _ => match word.strip_prefix('@') {
+                Some(_) => self
+                    .members
+                    .get(group)
+                    .map(|m| m.iter().map(|m| format!("@{}", m)).collect())
+                    .unwrap_or_default(),
+                None => self.members.get(group).cloned().unwrap_or_default(),
+            },
+        };
+
+        let candidates = candidates
+            .into_iter()
+            .filter(|c: &String| c.starts_with(word) && c != word)
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let index = if reverse { candidates.len() - 1 } else { 0 };
+        let completion =
+            Completion { delete: word.chars().count(), insert: candidates[index].clone() };
+        self.cycle = Some(Cycle { candidates, index });
+        Some(completion)
+    }
+}
@@ -0,0 +1,189 @@
+//! History of sent messages and commands for the Input Box.
+//! Every entry is kept in a global ring and in the ring of the buffer it was sent from, and is
+//! appended to a history file in the data directory so it survives restarts.
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::OpenOptions,
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use anyhow::Result;
+
+use crate::types::GroupId;
+
+/// Maximum amount of entries kept in a single ring
+const MAX_ENTRIES: usize = 1_000;
+
+/// The ring to recall entries from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ring {
+    /// Entries sent from the focused buffer
+    Buffer,
+    /// Entries sent from any buffer
+    Global,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    global: VecDeque<String>,
+    buffers: HashMap<GroupId, VecDeque<String>>,
+    /// Ring and offset from the newest entry that is currently recalled
+    position: Option<(Ring, usize)>,
+    /// Text of the input box before recalling started
+    draft: Option<String>,
+    path: Option<PathBuf>,
+}
+
+/// State of a Ctrl-r reverse incremental search through the global ring
+#[derive(Debug, Clone, Default)]
+pub struct ReverseSearch {
+    pub query: String,
+    /// index into the global ring of the current match
+    pub found: Option<usize>,
+    /// Text of the input box before the search started
+    pub draft: String,
+}
+
+impl History {
+    /// Load the history stored at `path`. Entries written after this are appended to the same
+    /// file.
+    pub fn load(path: PathBuf) -> Self {
+        let mut history = Self::default();
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                let lines = contents.lines().collect::<Vec<_>>();
+                let skip = lines.len().saturating_sub(MAX_ENTRIES);
+                for line in &lines[skip..] {
+                    if let Some((group, entry)) = Self::decode(line) {
+                        history.insert(group, entry);
+                    }
+                }
+                if skip > 0 {
+                    if let Err(e) = std::fs::write(&path, lines[skip..].join("\n") + "\n") {
+                        log::error!("Could not truncate history file {}", e);
+                    }
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => (),
+            Err(e) => log::error!("Could not read history file {}", e),
+        }
+        history.path = Some(path);
+        history
+    }
+
+    /// Record an entry sent from `group`, persisting it to the history file.
+    pub fn push(&mut self, group: &GroupId, entry: String) {
+        self.position = None;
+        self.draft = None;
+        if entry.trim().is_empty() {
+            return;
+        }
+        if let Err(e) = self.append(group, &entry) {
+            log::error!("Could not write to history file {}", e);
+        }
+        self.insert(group.clone(), entry);
+    }
+
+    /// Recall the entry before the one currently recalled. `current` is the text in the input box,
+    /// which is restored once recall moves past the newest entry again.
+    pub fn previous(&mut self, ring: Ring, group: &GroupId, current: &str) -> Option<String> {
+        let offset = match self.position {
+            Some((r, offset)) if r == ring => offset + 1,
+            _ => 0,
+        };
+        let entries = self.ring(ring, group)?;
+        let entry = entries.get(entries.len().checked_sub(offset + 1)?)?.clone();
+        if self.position.is_none() {
+            self.draft = Some(current.to_string());
+        }
+        self.position = Some((ring, offset));
+        Some(entry)
+    }
+
+    /// Recall the entry after the one currently recalled, or the draft if the newest entry is
+    /// recalled.
+    pub fn next(&mut self, ring: Ring, group: &GroupId) -> Option<String> {
+        match self.position {
+            Some((r, 0)) if r == ring => {
+                self.position = None;
+                self.draft.take()
+            }
+            Some((r, offset)) if r == ring => {
+                let entries = self.ring(ring, group)?;
+                let entry = entries.get(entries.len().checked_sub(offset)?)?.clone();
+                self.position = Some((ring, offset - 1));
+                Some(entry)
+            }
+            _ => None,
+        }
+    }
+
+    /// Find the newest entry of the global ring that contains `query`, starting at index `from`
+    /// and going back in time.
+    pub fn search_back(&self, query: &str, from: usize) -> Option<usize> {
+        let from = std::cmp::min(from, self.global.len().checked_sub(1)?);
+        (0..=from).rev().find(|i| self.global[*i].contains(query))
+    }
+
+    pub fn newest(&self) -> usize {
+        self.global.len().saturating_sub(1)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.global.get(index).map(String::as_str)
+    }
+
+    fn ring(&self, ring: Ring, group: &GroupId) -> Option<&VecDeque<String>> {
+        match ring {
+            Ring::Buffer => self.buffers.get(group),
+            Ring::Global => Some(&self.global),
+        }
+    }
+
+    fn insert(&mut self, group: GroupId, entry: String) {
+        let buffer = self.buffers.entry(group).or_default();
+        for ring in [buffer, &mut self.global] {
+            if ring.back() != Some(&entry) {
+                ring.push_back(entry.clone());
+            }
+            if ring.len() > MAX_ENTRIES {
+                ring.pop_front();
+            }
+        }
+    }
+
+    fn append(&self, group: &GroupId, entry: &str) -> Result<()> {
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", Self::encode(group, entry))?;
+        }
+        Ok(())
+    }
+
+    /// Entries are stored one per line as `<hex group id>\t<entry>`, with backslashes and
+    /// newlines in the entry escaped.
+    fn encode(group: &GroupId, entry: &str) -> String {
+        let entry = entry.replace('\\', "\\\\").replace('\n', "\\n");
+        format!("{}\t{}", hex::encode(group), entry)
+    }
+
+    fn decode(line: &str) -> Option<(GroupId, String)> {
+        let (group, escaped) = line.split_once('\t')?;
+        let group = hex::decode(group).ok()?;
+        let mut entry = String::with_capacity(escaped.len());
+        let mut chars = escaped.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                entry.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => entry.push('\n'),
+                Some(c) => entry.push(c),
+                None => entry.push('\\'),
+            }
+        }
+        Some((group, entry))
+    }
+}
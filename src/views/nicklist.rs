@@ -0,0 +1,175 @@
+//! Nicklist View
+//! Members of the focused group, shown on the right of the chat area. Toggled with Alt-n or
+//! `/nicklist`.
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    pin::Pin,
+};
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{prelude::*, widgets::*, Frame};
+use tokio::sync::mpsc::Sender;
+
+use crate::{
+    dispatch::{
+        mark_dirty, Action, ActionSender, ListCommand, RenderContext, Store, ViewRender, XMTPAction,
+    },
+    types::{Group, GroupId, Identity, Member},
+    util::table,
+};
+
+/// Width of the pane, including borders
+pub const NICKLIST_WIDTH: u16 = 20;
+
+#[derive(Debug, Clone)]
+pub struct Nicklist {
+    xmtp: Sender<XMTPAction>,
+    events: ActionSender,
+    members: HashMap<GroupId, Vec<Member>>,
+    focused_group: Group,
+    me: Option<Identity>,
+    visible: bool,
+    /// Whether a popup has taken over key presses
+    popup: bool,
+}
+
+impl Nicklist {
+    pub fn new(xmtp: Sender<XMTPAction>, events: ActionSender) -> Self {
+        Self {
+            xmtp,
+            events,
+            members: HashMap::new(),
+            focused_group: Group::new_fake(0),
+            me: None,
+            visible: true,
+            popup: false,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Toggles through an action, so the settings page knows whether the nicklist is shown
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        if key.modifiers.intersects(KeyModifiers::ALT) && key.code == KeyCode::Char('n') {
+            self.events.send(Action::ToggleNicklist)?;
+        }
+        Ok(())
+    }
+
+    /// Print every member of the groups we are in but us, with the amount of groups we share
+    fn list_users(&self) -> Result<()> {
+        let mut users: BTreeMap<&str, (&Member, usize)> = BTreeMap::new();
+        let is_me = |m: &Member| self.me.as_ref().is_some_and(|me| me.inbox_id == m.inbox_id);
+        for member in self.members.values().flatten().filter(|m| !is_me(m)) {
+            users.entry(&member.inbox_id).or_insert((member, 0)).1 += 1;
+        }
+        let mut rows = users
+            .into_values()
+            .map(|(member, groups)| {
+                [member.addresses.join(", "), member.inbox_id.clone(), groups.to_string()]
+            })
+            .collect::<Vec<_>>();
+        rows.sort_by_key(|[addresses, ..]| addresses.to_lowercase());
+        let msg = match rows.len() {
+            0 => "No other users in the groups you are in".to_string(),
+            n => format!("{} users{}", n, table(["Addresses", "Inbox id", "Groups"], &rows)),
+        };
+        self.events.send(Action::FakeMessage(vec![0], ("xchat".into(), msg)))?;
+        Ok(())
+    }
+
+    /// Name shown for a member, preferring their first wallet address
+    fn name(member: &Member) -> &str {
+        member.addresses.first().unwrap_or(&member.inbox_id)
+    }
+}
+
+impl Store for Nicklist {
+    fn handles(&self, action: &Action) -> bool {
+        matches!(
+            action,
+            Action::KeyPress(_)
+                | Action::SetPopup(_)
+                | Action::ToggleNicklist
+                | Action::Identity(_)
+                | Action::GroupMembers(..)
+                | Action::SetFocusedGroup(_)
+                | Action::List(ListCommand::Users)
+                | Action::ClearGroups
+        )
+    }
+
+    fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
+        let future = async move {
+            match action {
+                Action::KeyPress(key) if !self.popup => self.handle_key_event(key)?,
+                Action::SetPopup(open) => self.popup = open,
+                Action::ToggleNicklist => {
+                    self.visible = !self.visible;
+                    mark_dirty();
+                }
+                Action::Identity(identity) => self.me = Some(identity),
+                Action::GroupMembers(group_id, mut members) => {
+                    if group_id == self.focused_group.id {
+                        mark_dirty();
+                    }
+                    members.sort_by_key(|m| {
+                        (!m.is_super_admin, !m.is_admin, Self::name(m).to_lowercase())
+                    });
+                    self.members.insert(group_id, members);
+                }
+                Action::SetFocusedGroup(group) => {
+                    // membership may have changed while the group was not focused
+                    if !group.is_fake() {
+                        self.xmtp.send(XMTPAction::Members(group.clone())).await?;
+                    }
+                    self.focused_group = group;
+                    mark_dirty();
+                }
+                Action::List(ListCommand::Users) => self.list_users()?,
+                Action::ClearGroups => {
+                    self.members.clear();
+                    mark_dirty();
+                }
+                _ => (),
+            }
+            Ok(())
+        };
+        Box::pin(future)
+    }
+}
+
+impl ViewRender for Nicklist {
+    fn render(&self, frame: &mut Frame, render_ctx: RenderContext) {
+        let members = self.members.get(&self.focused_group.id).map(Vec::as_slice).unwrap_or(&[]);
+        let items = members
+            .iter()
+            .map(|member| {
+                let prefix = if member.is_super_admin {
+                    "~"
+                } else if member.is_admin {
+                    "@"
+                } else {
+                    " "
+                };
+                let item = ListItem::new(format!("{}{}", prefix, Self::name(member)));
+                let is_me = self.me.as_ref().is_some_and(|me| me.inbox_id == member.inbox_id);
+                if is_me {
+                    item.style(Style::new().fg(Color::LightGreen).add_modifier(Modifier::BOLD))
+                } else {
+                    item
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let title = format!("{} users", members.len());
+        frame.render_widget(
+            List::new(items).block(Block::new().borders(Borders::ALL).title(title)),
+            render_ctx.area,
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! Fuzzy Room Switcher Popup
+//! Opened with Ctrl-k. Fuzzy matches rooms by name, member and group id, listing the matches by
+//! recent activity.
+use std::{collections::HashMap, future::Future, pin::Pin};
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{layout::Flex, prelude::*, widgets::*, Frame};
+
+use crate::{
+    dispatch::{mark_dirty, Action, ActionSender, RenderContext, Store, ViewRender},
+    types::{Group, GroupId, GroupIdWrapper, LOG_BUFFER},
+};
+
+#[derive(Debug, Clone)]
+struct Room {
+    group: Group,
+    name: String,
+    /// Inbox ids and addresses of the members, and of users that have sent messages to this room
+    members: Vec<String>,
+    /// Timestamp of the last message in nano-seconds
+    last_activity: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RoomSwitcher {
+    rooms: HashMap<GroupId, Room>,
+    open: bool,
+    query: String,
+    /// index into the matching rooms
+    selected: usize,
+    events: ActionSender,
+    /// Whether a page is shown over the chat, which owns the popup and key presses until closed
+    covered: bool,
+}
+
+impl RoomSwitcher {
+    pub fn new(events: ActionSender) -> Self {
+        let rooms = [Group::new_fake(0), Group::new_fake(LOG_BUFFER)].map(|group| {
+            let room = Room { name: group.name(), group, members: Vec::new(), last_activity: 0 };
+            (room.group.id.clone(), room)
+        });
+        Self {
+            rooms: HashMap::from(rooms),
+            open: false,
+            query: String::new(),
+            selected: 0,
+            events,
+            covered: false,
+        }
+    }
+
+    pub fn set_covered(&mut self, covered: bool) {
+        self.covered = covered;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Rooms matching the query, most recently active first
+    fn matches(&self) -> Vec<&Room> {
+        let mut matches = self
+            .rooms
+            .values()
+            .filter_map(|room| {
+                let id = hex::encode(&room.group.id);
+                std::iter::once(&room.name)
+                    .chain(room.members.iter())
+                    .chain(std::iter::once(&id))
+                    .filter_map(|candidate| fuzzy_score(&self.query, candidate))
+                    .max()
+                    .map(|score| (room, score))
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|(a, a_score), (b, b_score)| {
+            b.last_activity.cmp(&a.last_activity).then(b_score.cmp(a_score))
+        });
+        matches.into_iter().map(|(room, _)| room).collect()
+    }
+
+    fn set_open(&mut self, open: bool) -> Result<()> {
+        self.open = open;
+        self.query.clear();
+        self.selected = 0;
+        self.events.send(Action::SetPopup(open))?;
+        Ok(())
+    }
+
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        let ctrl = key.modifiers.intersects(KeyModifiers::CONTROL);
+        if !self.open {
+            if ctrl && key.code == KeyCode::Char('k') {
+                self.set_open(true)?;
+            }
+            return Ok(());
+        }
+
+        match key.code {
+            KeyCode::Esc => self.set_open(false)?,
+            KeyCode::Char('k') if ctrl => self.set_open(false)?,
+            KeyCode::Enter => {
+                let group = self.matches().get(self.selected).map(|room| room.group.clone());
+                self.set_open(false)?;
+                if let Some(group) = group {
+                    self.events.send(Action::SetFocusedGroup(group))?;
+                }
+            }
+            KeyCode::Up => self.select_previous(),
+            KeyCode::Char('p') if ctrl => self.select_previous(),
+            KeyCode::Down => self.select_next(),
+            KeyCode::Char('n') if ctrl => self.select_next(),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.selected = 0;
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.selected = 0;
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn select_next(&mut self) {
+        let last = self.matches().len().saturating_sub(1);
+        self.selected = std::cmp::min(self.selected + 1, last);
+    }
+
+    fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    fn room(&mut self, id: &GroupId) -> &mut Room {
+        self.rooms.entry(id.clone()).or_insert_with(|| Room {
+            group: Group::new(id.clone(), 0, 0),
+            name: format!("{}", GroupIdWrapper::from(id.clone())),
+            members: Vec::new(),
+            last_activity: 0,
+        })
+    }
+
+    fn add_member(&mut self, id: &GroupId, user: &str) {
+        let room = self.room(id);
+        if !room.members.iter().any(|m| m == user) {
+            room.members.push(user.to_string());
+        }
+    }
+
+    fn add_activity(&mut self, id: &GroupId, user: &str, sent_at: i64) {
+        let room = self.room(id);
+        room.last_activity = std::cmp::max(room.last_activity, sent_at);
+        self.add_member(id, user);
+    }
+}
+
+/// Score how well `query` fuzzy matches `candidate`, if all characters of the query appear in order
+/// in the candidate. Consecutive characters and matches at the start score higher.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate = candidate.to_lowercase();
+    let mut chars = candidate.char_indices();
+    let mut score = 0;
+    let mut last = None;
+    for q in query.to_lowercase().chars() {
+        let (index, _) = chars.by_ref().find(|(_, c)| *c == q)?;
+        score += match last {
+            Some(last) if last + 1 == index => 3,
+            None if index == 0 => 2,
+            _ => 1,
+        };
+        last = Some(index);
+    }
+    Some(score)
+}
+
+impl Store for RoomSwitcher {
+    fn handles(&self, action: &Action) -> bool {
+        matches!(
+            action,
+            Action::KeyPress(_)
+                | Action::NewGroups(_)
+                | Action::ReceiveMessage(_)
+                | Action::ReceiveMessages(_)
+                | Action::GroupMembers(..)
+                | Action::GroupInfo(_)
+                | Action::ClearGroups
+        )
+    }
+
+    fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
+        let future = async move {
+            // the switcher is only drawn while open, or as it is opened and closed
+            let was_open = self.open;
+            match action {
+                Action::KeyPress(key) if !self.covered => self.handle_key_event(key).await?,
+                Action::NewGroups(groups) => {
+                    for group in groups {
+                        let last_sent_at = group.last_sent_at;
+                        let room = self.room(&group.id);
+                        room.last_activity = std::cmp::max(room.last_activity, last_sent_at);
+                        room.group = group;
+                    }
+                }
+                Action::ReceiveMessage(msg) => {
+                    self.add_activity(&msg.group_id, &msg.sender_inbox_id, msg.sent_at_ns)
+                }
+                Action::ReceiveMessages(messages) => {
+                    for msg in messages.values().flatten() {
+                        self.add_activity(&msg.group_id, &msg.sender_inbox_id, msg.sent_at_ns);
+                    }
+                }
+                Action::GroupMembers(group_id, members) => {
+                    for member in members {
+                        self.add_member(&group_id, &member.inbox_id);
+                        member.addresses.iter().for_each(|a| self.add_member(&group_id, a));
+                    }
+                }
+                Action::GroupInfo(info) if !info.name.is_empty() => {
+                    self.room(&info.id).name = info.name;
+                }
+                Action::ClearGroups => {
+                    self.rooms.retain(|_, room| room.group.is_fake());
+                    self.selected = 0;
+                }
+                _ => (),
+            };
+            if was_open || self.open {
+                mark_dirty();
+            }
+            Ok(())
+        };
+        Box::pin(future)
+    }
+}
+
+impl ViewRender for RoomSwitcher {
+    fn render(&self, frame: &mut Frame, render_ctx: RenderContext) {
+        let [area] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(render_ctx.area);
+        let [area] = Layout::vertical([Constraint::Percentage(50)]).flex(Flex::Center).areas(area);
+
+        let block = Block::new().borders(Borders::ALL).title("Switch Room");
+        let [query, rooms] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(block.inner(area));
+
+        let matches = self
+            .matches()
+            .into_iter()
+            .map(|room| ListItem::new(format!("{} ({} members)", room.name, room.members.len())))
+            .collect::<Vec<_>>();
+        let list = List::new(matches).highlight_style(Style::new().fg(Color::LightGreen));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        frame.render_widget(Paragraph::new(format!("> {}", self.query)), query);
+        frame.render_stateful_widget(
+            list,
+            rooms,
+            &mut ListState::default().with_selected(Some(self.selected)),
+        );
+    }
+}
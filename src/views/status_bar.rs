@@ -0,0 +1,199 @@
+//! Status Bar View
+//! A single line between the chat area and the input box, WeeChat style.
+use std::{collections::HashMap, future::Future, pin::Pin};
+
+use anyhow::Result;
+use ratatui::{prelude::*, widgets::*, Frame};
+
+use crate::{
+    dispatch::{mark_dirty, Action, RenderContext, Store, ViewRender},
+    types::{
+        ConnectionState, Group, GroupId, HotlistEntry, Identity, InputMode, Network, LOG_BUFFER,
+    },
+    views::activity_style,
+};
+
+#[derive(Debug, Clone)]
+pub struct StatusBar {
+    /// Current time, in hours and minutes
+    time: String,
+    me: Option<Identity>,
+    connection: Option<(Network, ConnectionState)>,
+    /// Buffers in the order they are numbered
+    groups: Vec<GroupId>,
+    focused_group: Group,
+    /// Amount of members in each group
+    members: HashMap<GroupId, usize>,
+    mode: InputMode,
+    popup: bool,
+    outbox: usize,
+    hotlist: Vec<HotlistEntry>,
+}
+
+impl Default for StatusBar {
+    fn default() -> Self {
+        let xchat = Group::new_fake(0);
+        Self {
+            time: Self::time(),
+            me: None,
+            connection: None,
+            groups: vec![xchat.id.clone(), vec![LOG_BUFFER]],
+            focused_group: xchat,
+            members: HashMap::new(),
+            mode: InputMode::default(),
+            popup: false,
+            outbox: 0,
+            hotlist: Vec::new(),
+        }
+    }
+}
+
+impl StatusBar {
+    fn time() -> String {
+        chrono::Local::now().format("%H:%M").to_string()
+    }
+
+    fn identity(&self) -> Span<'_> {
+        match &self.me {
+            // 0x1234…abcd
+            Some(me) if me.address.len() > 10 => {
+                Span::raw(format!("[{}…{}]", &me.address[..6], &me.address[me.address.len() - 4..]))
+            }
+            Some(me) => Span::raw(format!("[{}]", me.address)),
+            None => Span::raw("[no identity]"),
+        }
+    }
+
+    fn connection(&self) -> Span<'_> {
+        let Some((network, state)) = self.connection else {
+            return Span::raw("[offline]");
+        };
+        let color = match state {
+            ConnectionState::Connecting => Color::Yellow,
+            ConnectionState::Connected => Color::LightGreen,
+            ConnectionState::Disconnected => Color::LightRed,
+        };
+        Span::styled(format!("[{}:{}]", network, state), Style::new().fg(color))
+    }
+
+    fn buffer(&self) -> Span<'_> {
+        let group = &self.focused_group;
+        let number = self.groups.iter().position(|id| *id == group.id).map(|i| i + 1).unwrap_or(0);
+        let name = group.name();
+        match self.members.get(&group.id) {
+            Some(members) => Span::raw(format!("[{}:{} ({} members)]", number, name, members)),
+            None => Span::raw(format!("[{}:{}]", number, name)),
+        }
+    }
+
+    fn mode(&self) -> Span<'_> {
+        if self.popup {
+            return Span::styled("[popup]", Style::new().fg(Color::LightCyan));
+        }
+        Span::styled(format!("[{}]", self.mode), Style::new().fg(Color::LightCyan))
+    }
+
+    fn hotlist(&self) -> Vec<Span<'_>> {
+        if self.hotlist.is_empty() {
+            return Vec::new();
+        }
+        let mut spans = vec![Span::raw("[Act: ")];
+        for (i, entry) in self.hotlist.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(","));
+            }
+            let style = activity_style(entry.activity);
+            spans.push(Span::styled(format!("{}:{}", entry.number, entry.name), style));
+        }
+        spans.push(Span::raw("]"));
+        spans
+    }
+}
+
+impl Store for StatusBar {
+    fn handles(&self, action: &Action) -> bool {
+        matches!(
+            action,
+            Action::Identity(_)
+                | Action::Connection(..)
+                | Action::SetFocusedGroup(_)
+                | Action::NewGroups(_)
+                | Action::GroupMembers(..)
+                | Action::InputMode(_)
+                | Action::SetPopup(_)
+                | Action::Outbox(_)
+                | Action::Hotlist(_)
+                | Action::ClearGroups
+                | Action::Tick
+        )
+    }
+
+    fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
+        let future = async move {
+            match action {
+                Action::Identity(identity) => self.me = Some(identity),
+                Action::Connection(network, state) => self.connection = Some((network, state)),
+                Action::SetFocusedGroup(group) => self.focused_group = group,
+                Action::NewGroups(groups) => {
+                    for group in groups {
+                        if !self.groups.contains(&group.id) {
+                            self.groups.push(group.id);
+                        }
+                    }
+                }
+                Action::GroupMembers(group_id, members) => {
+                    self.members.insert(group_id, members.len());
+                }
+                Action::InputMode(mode) => self.mode = mode,
+                Action::SetPopup(open) => self.popup = open,
+                Action::Outbox(pending) => self.outbox = pending,
+                Action::Hotlist(hotlist) => self.hotlist = hotlist,
+                Action::ClearGroups => {
+                    // the xchat and log buffers are numbered first
+                    self.groups.truncate(2);
+                    self.members.clear();
+                }
+                Action::Tick => {
+                    let time = Self::time();
+                    if time != self.time {
+                        self.time = time;
+                        mark_dirty();
+                    }
+                    return Ok(());
+                }
+                _ => (),
+            }
+            // everything else handled is shown in the bar
+            mark_dirty();
+            Ok(())
+        };
+        Box::pin(future)
+    }
+}
+
+impl ViewRender for StatusBar {
+    fn render(&self, frame: &mut Frame, render_ctx: RenderContext) {
+        let mut sections = vec![
+            Span::raw(format!("[{}]", self.time)),
+            self.identity(),
+            self.connection(),
+            self.buffer(),
+            self.mode(),
+        ];
+        if self.outbox > 0 {
+            let outbox = format!("[outbox: {}]", self.outbox);
+            sections.push(Span::styled(outbox, Style::new().fg(Color::Yellow)));
+        }
+        let mut spans = Vec::new();
+        for section in sections {
+            spans.push(section);
+            spans.push(Span::raw(" "));
+        }
+        spans.extend(self.hotlist());
+        let line = Line::from(spans);
+        frame.render_widget(
+            Paragraph::new(line).style(Style::new().bg(Color::DarkGray)),
+            render_ctx.area,
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Toast View
+//! Shows the last error over the top right of the chat area for a few seconds.
+use std::{
+    future::Future,
+    pin::Pin,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use ratatui::{prelude::*, widgets::*, Frame};
+
+use crate::{
+    dispatch::{mark_dirty, Action, RenderContext, Store, ViewRender},
+    types::AppError,
+};
+
+/// How long an error is shown for
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+const TOAST_WIDTH: u16 = 50;
+
+#[derive(Debug, Clone, Default)]
+pub struct Toast {
+    error: Option<(AppError, Instant)>,
+}
+
+impl Toast {
+    pub fn is_visible(&self) -> bool {
+        self.error.as_ref().is_some_and(|(_, shown)| shown.elapsed() < TOAST_DURATION)
+    }
+}
+
+impl Store for Toast {
+    fn handles(&self, action: &Action) -> bool {
+        matches!(action, Action::Error(_) | Action::Tick)
+    }
+
+    fn update(&mut self, action: Action) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
+        let future = async move {
+            match action {
+                Action::Error(error) => {
+                    self.error = Some((error, Instant::now()));
+                    mark_dirty();
+                }
+                Action::Tick if self.error.is_some() && !self.is_visible() => {
+                    self.error = None;
+                    mark_dirty();
+                }
+                _ => (),
+            }
+            Ok(())
+        };
+        Box::pin(future)
+    }
+}
+
+impl ViewRender for Toast {
+    fn render(&self, frame: &mut Frame, render_ctx: RenderContext) {
+        let Some((error, _)) = &self.error else {
+            return;
+        };
+        let area = render_ctx.area;
+        let width = std::cmp::min(TOAST_WIDTH, area.width);
+        // the message wraps inside the borders
+        let lines = error.message.len() as u16 / width.saturating_sub(2).max(1) + 1;
+        let height = std::cmp::min(lines + 2, area.height);
+        let area = Rect::new(area.right().saturating_sub(width), area.y, width, height);
+
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .border_style(Style::new().fg(Color::LightRed))
+            .title(format!("{} error", error.source));
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(error.message.as_str()).block(block).wrap(Wrap { trim: true }),
+            area,
+        );
+    }
+}